@@ -1,25 +1,69 @@
 use anyhow::Result;
-use assessment::{cli::show::show_once, runtime::orderbook::Orderbook};
-use common::{config::Config, orderbook::orderbook_aggregator_server::OrderbookAggregatorServer};
+use assessment::{
+    cli::show::show_once,
+    runtime::{metrics, orderbook::Orderbook, providers::check, reload, shutdown, telemetry},
+};
+use common::{
+    config::Config,
+    orderbook::{
+        admin_server::AdminServer, orderbook_aggregator_server::OrderbookAggregatorServer,
+    },
+};
 use std::sync::Arc;
+use tokio::{sync::oneshot, task};
 use tonic::transport::Server;
+use tracing::error;
 
 #[tokio::main]
 pub async fn main() -> Result<()> {
-    env_logger::init();
+    let config = Config::load()?;
+    telemetry::init(&config)?;
 
-    let config = Config::as_ref();
-
-    if config.cli() {
+    if config.check_config() {
+        check(&config)?;
+        print!("{}", config.to_toml()?);
+    } else if config.cli() {
         show_once().await?;
     } else {
-        let orderbook = Orderbook::new(Arc::clone(&config));
+        check(&config)?;
+
+        let orderbook = Orderbook::new(Arc::clone(&config))?;
         orderbook.connect()?;
 
-        Server::builder()
-            .add_service(OrderbookAggregatorServer::new(orderbook))
-            .serve(config.local_bind())
-            .await?;
+        let providers = orderbook.providers();
+        let publisher = orderbook.publisher();
+
+        let watched = Arc::clone(&providers);
+        tokio::spawn(async move {
+            if let Err(e) = reload::watch(watched).await {
+                error!("configuration reload stopped - {}", e);
+            }
+        });
+        metrics::serve(providers.metrics(), config.metrics_bind())?;
+
+        let (stop, stopped) = oneshot::channel::<()>();
+        let mut server = tokio::spawn(
+            Server::builder()
+                .trace_fn(telemetry::request_span)
+                .add_service(AdminServer::new(orderbook.admin()))
+                .add_service(OrderbookAggregatorServer::new(orderbook))
+                .serve_with_shutdown(config.local_bind(), async {
+                    stopped.await.ok();
+                }),
+        );
+
+        tokio::select! {
+            served = &mut server => return Ok(served??),
+            signal = shutdown::signal() => signal?,
+        }
+
+        // streams end with a status first, so the server has nothing left to wait for
+        publisher.close();
+        stop.send(()).ok();
+        shutdown::drain(server, config.drain_timeout()).await?;
+
+        task::spawn_blocking(move || providers.shutdown()).await??;
+        telemetry::shutdown();
     }
     Ok(())
 }
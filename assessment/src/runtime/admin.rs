@@ -0,0 +1,126 @@
+use super::providers::Providers;
+use anyhow::Result;
+use common::orderbook::{
+    admin_server, DepthRequest, Empty, PairRequest, ProviderList, ProviderRequest, ProviderState,
+};
+use std::sync::Arc;
+use tokio::task;
+use tonic::{async_trait, Request, Response, Status};
+use tracing::info;
+
+/// Operator controls over the running providers
+pub struct Admin {
+    providers: Arc<Providers>,
+}
+
+impl Admin {
+    pub fn new(providers: Arc<Providers>) -> Self {
+        Self { providers }
+    }
+
+    fn list(providers: &Providers) -> ProviderList {
+        let config = providers.config();
+
+        ProviderList {
+            pair: String::from(config.pair()),
+            depth: config.top() as u32,
+            providers: providers.states(),
+        }
+    }
+
+    /// Connecting and subscribing block on the sockets, so they run off the async workers
+    ///
+    /// A failed operation is a `failed_precondition` status, a panicked one an `internal` status.
+    async fn blocking<T, F>(&self, operation: F) -> Result<Response<T>, Status>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Providers) -> Result<T> + Send + 'static,
+    {
+        let providers = Arc::clone(&self.providers);
+
+        match task::spawn_blocking(move || operation(&providers)).await {
+            Ok(Ok(response)) => Ok(Response::new(response)),
+            Ok(Err(e)) => Err(Status::failed_precondition(e.to_string())),
+            Err(e) => Err(Status::internal(e.to_string())),
+        }
+    }
+}
+
+#[async_trait]
+impl admin_server::Admin for Admin {
+    async fn list_providers(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<ProviderList>, Status> {
+        Ok(Response::new(Self::list(&self.providers)))
+    }
+
+    async fn subscribe(
+        &self,
+        request: Request<ProviderRequest>,
+    ) -> Result<Response<ProviderState>, Status> {
+        let name = request.into_inner().name;
+        info!("admin subscribe {}", name);
+
+        self.blocking(move |providers| providers.subscribe(name.as_str()))
+            .await
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: Request<ProviderRequest>,
+    ) -> Result<Response<ProviderState>, Status> {
+        let name = request.into_inner().name;
+        info!("admin unsubscribe {}", name);
+
+        self.blocking(move |providers| providers.unsubscribe(name.as_str()))
+            .await
+    }
+
+    async fn reconnect(
+        &self,
+        request: Request<ProviderRequest>,
+    ) -> Result<Response<ProviderState>, Status> {
+        let name = request.into_inner().name;
+        info!("admin reconnect {}", name);
+
+        self.blocking(move |providers| providers.reconnect(name.as_str()))
+            .await
+    }
+
+    async fn set_pair(
+        &self,
+        request: Request<PairRequest>,
+    ) -> Result<Response<ProviderList>, Status> {
+        let pair = request.into_inner().pair;
+        info!("admin pair {}", pair);
+
+        if pair.is_empty() {
+            return Err(Status::invalid_argument("pair is empty"));
+        }
+
+        self.blocking(move |providers| {
+            providers.reconfigure(|config| Ok(config.with_pair(pair.as_str())))?;
+            Ok(Self::list(providers))
+        })
+        .await
+    }
+
+    async fn set_depth(
+        &self,
+        request: Request<DepthRequest>,
+    ) -> Result<Response<ProviderList>, Status> {
+        let depth = request.into_inner().depth;
+        info!("admin depth {}", depth);
+
+        if depth == 0 {
+            return Err(Status::invalid_argument("depth must be positive"));
+        }
+
+        self.blocking(move |providers| {
+            providers.reconfigure(|config| Ok(config.with_top(depth as usize)))?;
+            Ok(Self::list(providers))
+        })
+        .await
+    }
+}
@@ -0,0 +1,242 @@
+use super::{merge, metrics::MetricsRef};
+use common::{
+    clock,
+    orderbook::{Arbitrage, Phase, Summary},
+    ConfigRef,
+};
+use std::{collections::BTreeMap, mem, time::Duration};
+use tracing::info;
+
+/// Open window, with what was last sent about it
+struct Window {
+    start: u64,
+    last: Arbitrage,
+}
+
+/// Watches the best levels of every exchange for a bid crossing another exchange's ask
+///
+/// Each pair of exchanges opens a window when it pays after both taker fees, sends an update
+/// whenever the crossed levels change and ends it when it no longer pays.
+pub struct Detector {
+    metrics: MetricsRef,
+    /// Keyed by the exchanges to buy from and sell to
+    windows: BTreeMap<(String, String), Window>,
+}
+
+impl Detector {
+    pub fn new(metrics: MetricsRef) -> Self {
+        Self {
+            metrics,
+            windows: BTreeMap::new(),
+        }
+    }
+
+    /// Events since the previous merged book
+    pub fn detect(&mut self, config: &ConfigRef, book: &Summary) -> Vec<Arbitrage> {
+        self.detect_at(config, book, clock::micros())
+    }
+
+    fn detect_at(&mut self, config: &ConfigRef, book: &Summary, now: u64) -> Vec<Arbitrage> {
+        let mut found = opportunities(config, book);
+        let mut events = Vec::new();
+
+        for (key, mut window) in mem::take(&mut self.windows) {
+            let mut arbitrage = match found.remove(&key) {
+                Some(arbitrage) => arbitrage,
+                None => {
+                    events.push(self.end(window, now));
+                    continue;
+                }
+            };
+
+            arbitrage.phase = Phase::Update as i32;
+            arbitrage.start_timestamp = window.start;
+            arbitrage.duration = now.saturating_sub(window.start);
+
+            let last = &window.last;
+            if (last.ask, last.bid, last.amount) != (arbitrage.ask, arbitrage.bid, arbitrage.amount)
+            {
+                window.last = arbitrage.clone();
+                events.push(arbitrage);
+            }
+            self.windows.insert(key, window);
+        }
+
+        for (key, mut arbitrage) in found {
+            info!(
+                buy = arbitrage.buy,
+                sell = arbitrage.sell,
+                edge = arbitrage.edge,
+                event = "arbitrage",
+                "window opened"
+            );
+
+            arbitrage.start_timestamp = now;
+            let window = Window {
+                start: now,
+                last: arbitrage.clone(),
+            };
+            self.windows.insert(key, window);
+            events.push(arbitrage);
+        }
+
+        events
+    }
+
+    fn end(&self, window: Window, now: u64) -> Arbitrage {
+        let mut arbitrage = window.last;
+        arbitrage.phase = Phase::End as i32;
+        arbitrage.duration = now.saturating_sub(window.start);
+
+        let duration = Duration::from_micros(arbitrage.duration);
+        self.metrics
+            .arbitrage(&arbitrage.buy, &arbitrage.sell, duration);
+        info!(
+            buy = arbitrage.buy,
+            sell = arbitrage.sell,
+            event = "arbitrage",
+            "window closed after {:?}",
+            duration
+        );
+
+        arbitrage
+    }
+}
+
+/// Every pair of exchanges whose crossed tops pay more than the configured edge
+fn opportunities(config: &ConfigRef, book: &Summary) -> BTreeMap<(String, String), Arbitrage> {
+    let tops = merge::tops(book);
+    let mut found = BTreeMap::new();
+
+    for (buy, ask) in tops.iter().filter_map(|(buy, top)| Some((buy, top.ask?))) {
+        for (sell, bid) in tops.iter().filter_map(|(sell, top)| Some((sell, top.bid?))) {
+            if buy == sell || bid.price <= ask.price {
+                continue;
+            }
+
+            let cost = ask.price * (1.0 + config.fees(buy).taker() / 1e4);
+            let proceeds = bid.price * (1.0 - config.fees(sell).taker() / 1e4);
+            let edge = (proceeds - cost) / cost * 1e4;
+            if edge <= config.arbitrage_edge() {
+                continue;
+            }
+
+            let amount = ask.amount.min(bid.amount);
+            let arbitrage = Arbitrage {
+                phase: Phase::Start as i32,
+                buy: String::from(*buy),
+                sell: String::from(*sell),
+                ask: ask.price,
+                bid: bid.price,
+                amount,
+                profit: (proceeds - cost) * amount,
+                edge,
+                ..Default::default()
+            };
+            found.insert((String::from(*buy), String::from(*sell)), arbitrage);
+        }
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use common::{config::Config, orderbook::Level};
+    use std::sync::Arc;
+
+    fn level(exchange: &str, price: f64, amount: f64) -> Level {
+        Level {
+            exchange: String::from(exchange),
+            price,
+            amount,
+            ..Default::default()
+        }
+    }
+
+    /// Bitstamp bids above the binance ask by `cross`
+    fn book(cross: f64, amount: f64) -> Summary {
+        Summary {
+            bids: vec![
+                level("Bitstamp", 100.0 + cross, amount),
+                level("Binance", 99.0, 1.0),
+            ],
+            asks: vec![
+                level("Binance", 100.0, 2.0),
+                level("Bitstamp", 101.0 + cross, 1.0),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn config() -> Result<ConfigRef> {
+        let fees = "[exchange.binance.fees]\ntaker = 10\n\n[exchange.bitstamp.fees]\ntaker = 10\n";
+        Ok(Arc::new(Config::load_toml(fees, ["algo"])?))
+    }
+
+    fn phase(event: &Arbitrage) -> Phase {
+        Phase::from_i32(event.phase).unwrap_or(Phase::Start)
+    }
+
+    #[test]
+    fn test_window() -> Result<()> {
+        let config = config()?;
+        let mut detector = Detector::new(Arc::default());
+
+        assert!(detector
+            .detect_at(&config, &book(0.0, 1.0), 1_000)
+            .is_empty());
+
+        let events = detector.detect_at(&config, &book(1.0, 1.5), 2_000);
+        assert_eq!(events.len(), 1);
+        let start = &events[0];
+        assert_eq!(phase(start), Phase::Start);
+        assert_eq!(
+            (start.buy.as_str(), start.sell.as_str()),
+            ("Binance", "Bitstamp")
+        );
+        assert_eq!(start.amount, 1.5);
+        // 101 * 0.999 - 100 * 1.001 per unit
+        assert!((start.profit - 0.799 * 1.5).abs() < 1e-9);
+        assert!((start.edge - 0.799 / 100.1 * 1e4).abs() < 1e-9);
+        assert_eq!(start.start_timestamp, 2_000);
+
+        // same levels, nothing to say
+        assert!(detector
+            .detect_at(&config, &book(1.0, 1.5), 3_000)
+            .is_empty());
+
+        let events = detector.detect_at(&config, &book(1.0, 3.0), 4_000);
+        assert_eq!(phase(&events[0]), Phase::Update);
+        assert_eq!(events[0].amount, 2.0);
+        assert_eq!(events[0].duration, 2_000);
+
+        let events = detector.detect_at(&config, &book(0.0, 3.0), 7_000);
+        assert_eq!(phase(&events[0]), Phase::End);
+        assert_eq!(events[0].amount, 2.0);
+        assert_eq!(events[0].duration, 5_000);
+
+        let text = detector.metrics.render()?;
+        assert!(text
+            .contains("algo_arbitrage_window_seconds_count{buy=\"Binance\",sell=\"Bitstamp\"} 1"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fees_eat_the_cross() -> Result<()> {
+        let mut detector = Detector::new(Arc::default());
+
+        // 0.1 across pays 0.1% gross, less than the 0.2% of fees
+        assert!(detector
+            .detect_at(&config()?, &book(0.1, 1.0), 1_000)
+            .is_empty());
+
+        let config = Arc::new(Config::load_from(["algo"])?);
+        assert_eq!(detector.detect_at(&config, &book(0.1, 1.0), 2_000).len(), 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,311 @@
+use super::metrics::MetricsRef;
+use common::orderbook::Summary;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+/// Exchanges needed before a median means anything, with two each one strays from the other
+const QUORUM: usize = 3;
+
+#[derive(Default)]
+struct Venue {
+    /// Start of the current streak away from the median
+    since: Option<Instant>,
+    excluded: bool,
+}
+
+/// Leaves out of the merge an exchange whose mid strays from the cross-exchange median
+///
+/// A venue is excluded once it stayed beyond the threshold for the whole window, and admitted again
+/// as soon as it is back within it or once it sends no book. Venues are told apart by the exchange
+/// of their levels.
+pub struct Breaker {
+    metrics: MetricsRef,
+    venues: BTreeMap<String, Venue>,
+}
+
+impl Breaker {
+    pub fn new(metrics: MetricsRef) -> Self {
+        Self {
+            metrics,
+            venues: BTreeMap::new(),
+        }
+    }
+
+    /// Books to merge, and the exchanges left out, `threshold` in basis points
+    pub fn filter(
+        &mut self,
+        summaries: Vec<Summary>,
+        threshold: f64,
+        window: Duration,
+    ) -> (Vec<Summary>, Vec<String>) {
+        self.filter_at(summaries, threshold, window, Instant::now())
+    }
+
+    fn filter_at(
+        &mut self,
+        summaries: Vec<Summary>,
+        threshold: f64,
+        window: Duration,
+        now: Instant,
+    ) -> (Vec<Summary>, Vec<String>) {
+        if threshold == 0.0 {
+            self.reset();
+            return (summaries, Vec::new());
+        }
+
+        self.forget_absent(&summaries);
+
+        let mids: Vec<(&str, f64)> = summaries.iter().filter_map(mid).collect();
+
+        if let Some(median) = median(mids.iter().map(|(_, mid)| *mid).collect()) {
+            for (exchange, mid) in mids.iter() {
+                let deviation = (mid - median).abs() / median * 1e4;
+                self.judge(exchange, deviation > threshold, deviation, window, now);
+            }
+        } else {
+            // nothing to judge against, streaks start over but exclusions hold
+            self.venues
+                .values_mut()
+                .for_each(|venue| venue.since = None);
+        }
+
+        let excluded: Vec<String> = self
+            .venues
+            .iter()
+            .filter(|(_, venue)| venue.excluded)
+            .map(|(exchange, _)| exchange.clone())
+            .collect();
+
+        let summaries = summaries
+            .into_iter()
+            .filter(|summary| match exchange(summary) {
+                Some(exchange) => !excluded.iter().any(|excluded| excluded == exchange),
+                None => true,
+            })
+            .collect();
+
+        (summaries, excluded)
+    }
+
+    fn judge(
+        &mut self,
+        exchange: &str,
+        strays: bool,
+        deviation: f64,
+        window: Duration,
+        now: Instant,
+    ) {
+        let venue = self.venues.entry(String::from(exchange)).or_default();
+
+        if !strays {
+            venue.since = None;
+            if venue.excluded {
+                venue.excluded = false;
+                self.metrics.excluded(exchange, false);
+                info!(exchange, event = "breaker", deviation, "admitted again");
+            }
+            return;
+        }
+
+        let since = *venue.since.get_or_insert(now);
+        if !venue.excluded && now.duration_since(since) >= window {
+            venue.excluded = true;
+            self.metrics.excluded(exchange, true);
+            warn!(
+                exchange,
+                event = "breaker",
+                deviation,
+                "excluded from the merge"
+            );
+        }
+    }
+
+    /// Starts over with the exchanges missing from the books, disconnected or failing
+    fn forget_absent(&mut self, summaries: &[Summary]) {
+        let present: Vec<&str> = summaries.iter().filter_map(exchange).collect();
+        let metrics = &self.metrics;
+
+        self.venues.retain(|exchange, venue| {
+            if present.contains(&exchange.as_str()) {
+                return true;
+            }
+            if venue.excluded {
+                metrics.excluded(exchange, false);
+                info!(
+                    exchange = exchange.as_str(),
+                    event = "breaker",
+                    "admitted again, sent no book"
+                );
+            }
+            false
+        });
+    }
+
+    /// Admits every exchange again, once the breaker is disabled
+    fn reset(&mut self) {
+        for (exchange, _) in self.venues.iter().filter(|(_, venue)| venue.excluded) {
+            self.metrics.excluded(exchange, false);
+            info!(
+                exchange = exchange.as_str(),
+                event = "breaker",
+                "admitted again"
+            );
+        }
+        self.venues.clear();
+    }
+}
+
+fn exchange(summary: &Summary) -> Option<&str> {
+    summary
+        .bids
+        .first()
+        .or_else(|| summary.asks.first())
+        .map(|level| level.exchange.as_str())
+}
+
+/// Exchange of the book and its mid, none for a one-sided book
+fn mid(summary: &Summary) -> Option<(&str, f64)> {
+    let bid = summary.bids.first()?;
+    let ask = summary.asks.first()?;
+    Some((bid.exchange.as_str(), (bid.price + ask.price) / 2.0))
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.len() < QUORUM {
+        return None;
+    }
+
+    values.sort_by(f64::total_cmp);
+    let middle = values.len() / 2;
+
+    match values.len() % 2 {
+        0 => Some((values[middle - 1] + values[middle]) / 2.0),
+        _ => Some(values[middle]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::orderbook::Level;
+    use std::sync::Arc;
+
+    const WINDOW: Duration = Duration::from_secs(5);
+
+    fn book(exchange: &str, bid: f64, ask: f64) -> Summary {
+        let level = |price| Level {
+            exchange: String::from(exchange),
+            price,
+            amount: 1.0,
+            ..Default::default()
+        };
+        Summary {
+            bids: vec![level(bid)],
+            asks: vec![level(ask)],
+            ..Default::default()
+        }
+    }
+
+    fn books(htx: f64) -> Vec<Summary> {
+        vec![
+            book("Binance", 0.0649, 0.0651),
+            book("Bitstamp", 0.0650, 0.0652),
+            book("HTX", htx - 0.0001, htx + 0.0001),
+        ]
+    }
+
+    fn names(summaries: &[Summary]) -> Vec<&str> {
+        summaries.iter().filter_map(exchange).collect()
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(vec![1.0, 2.0]), None);
+        assert_eq!(median(vec![3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(vec![4.0, 1.0, 3.0, 2.0]), Some(2.5));
+    }
+
+    #[test]
+    fn test_exclude_after_window() {
+        let mut breaker = Breaker::new(Arc::default());
+        let start = Instant::now();
+
+        // 20% away, but not for long enough yet
+        let (summaries, excluded) = breaker.filter_at(books(0.078), 500.0, WINDOW, start);
+        assert_eq!(names(&summaries), ["Binance", "Bitstamp", "HTX"]);
+        assert!(excluded.is_empty());
+
+        let (summaries, excluded) = breaker.filter_at(books(0.078), 500.0, WINDOW, start + WINDOW);
+        assert_eq!(names(&summaries), ["Binance", "Bitstamp"]);
+        assert_eq!(excluded, ["HTX"]);
+
+        // back in line, admitted at once
+        let (summaries, excluded) =
+            breaker.filter_at(books(0.0651), 500.0, WINDOW, start + WINDOW * 2);
+        assert_eq!(names(&summaries), ["Binance", "Bitstamp", "HTX"]);
+        assert!(excluded.is_empty());
+
+        let text = breaker.metrics.render().unwrap();
+        assert!(text.contains("algo_excluded{exchange=\"HTX\"} 0"));
+    }
+
+    #[test]
+    fn test_forget_absent() {
+        let mut breaker = Breaker::new(Arc::default());
+        let start = Instant::now();
+
+        breaker.filter_at(books(0.078), 500.0, WINDOW, start);
+        let (_, excluded) = breaker.filter_at(books(0.078), 500.0, WINDOW, start + WINDOW);
+        assert_eq!(excluded, ["HTX"]);
+
+        // HTX stops sending books, its exclusion goes with it
+        let mut without = books(0.078);
+        without.pop();
+        let (_, excluded) = breaker.filter_at(without, 500.0, WINDOW, start + WINDOW * 2);
+        assert!(excluded.is_empty());
+
+        let text = breaker.metrics.render().unwrap();
+        assert!(text.contains("algo_excluded{exchange=\"HTX\"} 0"));
+    }
+
+    #[test]
+    fn test_glitch_shorter_than_window() {
+        let mut breaker = Breaker::new(Arc::default());
+        let start = Instant::now();
+
+        breaker.filter_at(books(0.078), 500.0, WINDOW, start);
+        breaker.filter_at(books(0.0651), 500.0, WINDOW, start + WINDOW / 2);
+        let (_, excluded) = breaker.filter_at(books(0.078), 500.0, WINDOW, start + WINDOW);
+
+        assert!(excluded.is_empty());
+    }
+
+    #[test]
+    fn test_without_quorum() {
+        let mut breaker = Breaker::new(Arc::default());
+        let start = Instant::now();
+
+        let two = vec![book("Binance", 0.0649, 0.0651), book("HTX", 0.0779, 0.0781)];
+        breaker.filter_at(two.clone(), 500.0, WINDOW, start);
+        let (summaries, excluded) = breaker.filter_at(two, 500.0, WINDOW, start + WINDOW);
+
+        assert_eq!(summaries.len(), 2);
+        assert!(excluded.is_empty());
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut breaker = Breaker::new(Arc::default());
+        let start = Instant::now();
+
+        breaker.filter_at(books(0.078), 500.0, WINDOW, start);
+        breaker.filter_at(books(0.078), 500.0, WINDOW, start + WINDOW);
+
+        let (summaries, excluded) = breaker.filter_at(books(0.078), 0.0, WINDOW, start + WINDOW);
+        assert_eq!(summaries.len(), 3);
+        assert!(excluded.is_empty());
+    }
+}
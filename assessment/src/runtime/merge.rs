@@ -1,20 +1,56 @@
-use std::ops::Sub;
+use std::{collections::BTreeMap, ops::Sub};
 
-use common::{orderbook::Summary, ConfigRef};
+use common::{
+    clock,
+    orderbook::{Level, Summary},
+    ConfigRef,
+};
+use tracing::{field, instrument, Span};
 
-pub fn merge(config: ConfigRef, summaries: Vec<Summary>) -> Summary {
-    let mut summary = Summary::default();
+/// Every level of the books ranked best first, what quotes walk
+#[instrument(skip_all, fields(pair = config.pair(), books = summaries.len(), bids = field::Empty, asks = field::Empty))]
+pub fn book(config: &ConfigRef, summaries: Vec<Summary>) -> Summary {
+    let mut book = Summary::default();
 
     for s in summaries {
-        summary.asks.extend(s.asks);
-        summary.bids.extend(s.bids);
+        book.event_timestamp = oldest(book.event_timestamp, s.event_timestamp);
+        book.receive_timestamp = oldest(book.receive_timestamp, s.receive_timestamp);
+        book.asks.extend(s.asks);
+        book.bids.extend(s.bids);
     }
 
-    summary.asks.sort_by(|x, y| x.price.total_cmp(&y.price));
-    summary.asks.truncate(config.top());
+    // ranks by what hitting the level costs, the raw price when fees are left out
+    let rank = |level: &Level| match config.fee_adjusted() {
+        true => level.effective_price,
+        false => level.price,
+    };
+
+    if config.fee_adjusted() {
+        apply_fees(config, &mut book);
+    }
+
+    book.asks.sort_by(|x, y| rank(x).total_cmp(&rank(y)));
+    book.bids.sort_by(|x, y| rank(y).total_cmp(&rank(x)));
 
-    summary.bids.sort_by(|x, y| y.price.total_cmp(&x.price));
-    summary.bids.truncate(config.top());
+    book.merge_timestamp = clock::micros();
+
+    let span = Span::current();
+    span.record("bids", book.bids.len());
+    span.record("asks", book.asks.len());
+
+    book
+}
+
+/// Best `top` levels of a merged book, the summary clients get
+pub fn top(config: &ConfigRef, book: &Summary) -> Summary {
+    let mut summary = Summary {
+        bids: book.bids.iter().take(config.top()).cloned().collect(),
+        asks: book.asks.iter().take(config.top()).cloned().collect(),
+        event_timestamp: book.event_timestamp,
+        receive_timestamp: book.receive_timestamp,
+        merge_timestamp: book.merge_timestamp,
+        ..Default::default()
+    };
 
     summary.spread = match (summary.asks.last(), summary.bids.first()) {
         (Some(ask), Some(bid)) => ask.price.sub(bid.price),
@@ -23,3 +59,140 @@ pub fn merge(config: ConfigRef, summaries: Vec<Summary>) -> Summary {
 
     summary
 }
+
+/// Best bid and ask of one exchange in a merged book
+#[derive(Clone, Copy, Default)]
+pub struct Top<'a> {
+    pub bid: Option<&'a Level>,
+    pub ask: Option<&'a Level>,
+}
+
+/// Best levels of every exchange in a merged book, by raw price whatever the ranking
+pub fn tops(book: &Summary) -> BTreeMap<&str, Top<'_>> {
+    let mut tops = BTreeMap::<&str, Top>::new();
+
+    for level in book.bids.iter() {
+        let top = tops.entry(&level.exchange).or_default();
+        if !top.bid.is_some_and(|bid| bid.price >= level.price) {
+            top.bid = Some(level);
+        }
+    }
+    for level in book.asks.iter() {
+        let top = tops.entry(&level.exchange).or_default();
+        if !top.ask.is_some_and(|ask| ask.price <= level.price) {
+            top.ask = Some(level);
+        }
+    }
+
+    tops
+}
+
+/// Sets the effective price of every level, bids net of the taker fee and asks with it added
+fn apply_fees(config: &ConfigRef, summary: &mut Summary) {
+    let mut takers = BTreeMap::new();
+    let mut taker = |exchange: &str| -> f64 {
+        *takers
+            .entry(String::from(exchange))
+            .or_insert_with(|| config.fees(exchange).taker() / 1e4)
+    };
+
+    for level in summary.bids.iter_mut() {
+        level.effective_price = level.price * (1.0 - taker(&level.exchange));
+    }
+    for level in summary.asks.iter_mut() {
+        level.effective_price = level.price * (1.0 + taker(&level.exchange));
+    }
+}
+
+/// Earliest known timestamp, zero stands for unknown
+fn oldest(x: u64, y: u64) -> u64 {
+    match (x, y) {
+        (0, _) => y,
+        (_, 0) => x,
+        _ => x.min(y),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use common::config::Config;
+    use std::sync::Arc;
+
+    fn level(exchange: &str, price: f64) -> Level {
+        Level {
+            exchange: String::from(exchange),
+            price,
+            amount: 1.0,
+            ..Default::default()
+        }
+    }
+
+    fn books() -> Vec<Summary> {
+        vec![
+            Summary {
+                bids: vec![level("Binance", 100.0)],
+                asks: vec![level("Binance", 101.0)],
+                ..Default::default()
+            },
+            Summary {
+                bids: vec![level("Bitstamp", 100.1)],
+                asks: vec![level("Bitstamp", 100.9)],
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn config(args: &[&str]) -> Result<ConfigRef> {
+        let fees = "[exchange.binance.fees]\ntaker = 10\n\n[exchange.bitstamp.fees]\ntaker = 40\n";
+        let argv = ["algo"].iter().chain(args);
+
+        Ok(Arc::new(Config::load_toml(fees, argv.copied())?))
+    }
+
+    fn merge(config: ConfigRef) -> Result<Summary> {
+        Ok(top(&config, &book(&config, books())))
+    }
+
+    #[test]
+    fn test_top() -> Result<()> {
+        let config = config(&["--top", "1"])?;
+        let book = book(&config, books());
+        let summary = top(&config, &book);
+
+        assert_eq!(book.bids.len(), 2);
+        assert_eq!(summary.bids, [level("Bitstamp", 100.1)]);
+        assert_eq!(summary.asks, [level("Bitstamp", 100.9)]);
+        assert!((summary.spread - 0.8).abs() < 1e-9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_prices() -> Result<()> {
+        let summary = merge(config(&[])?)?;
+
+        assert_eq!(summary.bids[0], level("Bitstamp", 100.1));
+        assert_eq!(summary.asks[0], level("Bitstamp", 100.9));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fee_adjusted() -> Result<()> {
+        let summary = merge(config(&["--fee-adjusted"])?)?;
+
+        // the dearer bitstamp fee outweighs its better quotes
+        assert_eq!(summary.bids[0].exchange, "Binance");
+        assert_eq!(summary.bids[0].price, 100.0);
+        assert!((summary.bids[0].effective_price - 99.9).abs() < 1e-9);
+        assert!((summary.bids[1].effective_price - 99.6996).abs() < 1e-9);
+
+        assert_eq!(summary.asks[0].exchange, "Binance");
+        assert!((summary.asks[0].effective_price - 101.101).abs() < 1e-9);
+        assert!((summary.asks[1].effective_price - 101.3036).abs() < 1e-9);
+
+        Ok(())
+    }
+}
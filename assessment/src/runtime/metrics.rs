@@ -0,0 +1,388 @@
+use anyhow::Result;
+use hdrhistogram::Histogram;
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode,
+};
+use parking_lot::Mutex;
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+pub type MetricsRef = Arc<Metrics>;
+
+/// Latency stages, from the exchange event to the gRPC send
+pub const EVENT_TO_RECEIVE: &str = "event_to_receive";
+pub const RECEIVE_TO_MERGE: &str = "receive_to_merge";
+pub const MERGE_TO_SEND: &str = "merge_to_send";
+
+const QUANTILES: [f64; 4] = [0.5, 0.9, 0.99, 0.999];
+
+/// Feed, merge and gRPC health, rendered in the Prometheus text format
+pub struct Metrics {
+    registry: Registry,
+    updates: IntCounterVec,
+    parse_errors: IntCounterVec,
+    violations: IntCounterVec,
+    quarantined: IntGaugeVec,
+    excluded: IntGaugeVec,
+    reconnects: IntCounterVec,
+    update_age: GaugeVec,
+    merge_duration: HistogramVec,
+    arbitrage: HistogramVec,
+    published: IntCounter,
+    clients: IntGauge,
+    spread: GaugeVec,
+    latency_quantiles: GaugeVec,
+    latency_count: IntGaugeVec,
+    last_update: Mutex<BTreeMap<String, Instant>>,
+    latency: Mutex<BTreeMap<(&'static str, String), Histogram<u64>>>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new().expect("metrics")
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self> {
+        let registry = Registry::new_custom(Some(String::from("algo")), None)?;
+
+        let updates = IntCounterVec::new(
+            Opts::new("updates_total", "Messages received per provider"),
+            &["exchange"],
+        )?;
+        let parse_errors = IntCounterVec::new(
+            Opts::new("parse_errors_total", "Messages a provider failed to read"),
+            &["exchange"],
+        )?;
+        let violations = IntCounterVec::new(
+            Opts::new(
+                "validation_violations_total",
+                "Levels or books rejected by validation",
+            ),
+            &["exchange", "kind"],
+        )?;
+        let quarantined = IntGaugeVec::new(
+            Opts::new(
+                "quarantined",
+                "One while the exchange is left out of the merge",
+            ),
+            &["exchange"],
+        )?;
+        let excluded = IntGaugeVec::new(
+            Opts::new(
+                "excluded",
+                "One while the circuit breaker leaves the exchange out of the merge",
+            ),
+            &["exchange"],
+        )?;
+        let reconnects = IntCounterVec::new(
+            Opts::new(
+                "reconnects_total",
+                "Connections created after the first one",
+            ),
+            &["exchange"],
+        )?;
+        let update_age = GaugeVec::new(
+            Opts::new("update_age_seconds", "Seconds since the last update"),
+            &["exchange"],
+        )?;
+        let merge_duration = HistogramVec::new(
+            HistogramOpts::new("merge_duration_seconds", "Time to merge the books")
+                .buckets(vec![1e-5, 5e-5, 1e-4, 5e-4, 1e-3, 5e-3, 1e-2]),
+            &["pair"],
+        )?;
+        let arbitrage = HistogramVec::new(
+            HistogramOpts::new(
+                "arbitrage_window_seconds",
+                "How long arbitrage windows between two exchanges lasted",
+            )
+            .buckets(vec![0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 60.0]),
+            &["buy", "sell"],
+        )?;
+        let published = IntCounter::new("published_total", "Merged summaries published")?;
+        let clients = IntGauge::new("grpc_clients", "Connected streaming clients")?;
+        let spread = GaugeVec::new(Opts::new("spread", "Current merged spread"), &["pair"])?;
+        let latency_quantiles = GaugeVec::new(
+            Opts::new("latency_microseconds", "Latency quantiles since start"),
+            &["stage", "exchange", "quantile"],
+        )?;
+        let latency_count = IntGaugeVec::new(
+            Opts::new("latency_microseconds_count", "Latency samples since start"),
+            &["stage", "exchange"],
+        )?;
+
+        registry.register(Box::new(updates.clone()))?;
+        registry.register(Box::new(parse_errors.clone()))?;
+        registry.register(Box::new(violations.clone()))?;
+        registry.register(Box::new(quarantined.clone()))?;
+        registry.register(Box::new(excluded.clone()))?;
+        registry.register(Box::new(reconnects.clone()))?;
+        registry.register(Box::new(update_age.clone()))?;
+        registry.register(Box::new(merge_duration.clone()))?;
+        registry.register(Box::new(arbitrage.clone()))?;
+        registry.register(Box::new(published.clone()))?;
+        registry.register(Box::new(clients.clone()))?;
+        registry.register(Box::new(spread.clone()))?;
+        registry.register(Box::new(latency_quantiles.clone()))?;
+        registry.register(Box::new(latency_count.clone()))?;
+
+        Ok(Self {
+            registry,
+            updates,
+            parse_errors,
+            violations,
+            quarantined,
+            excluded,
+            reconnects,
+            update_age,
+            merge_duration,
+            arbitrage,
+            published,
+            clients,
+            spread,
+            latency_quantiles,
+            latency_count,
+            last_update: Mutex::default(),
+            latency: Mutex::default(),
+        })
+    }
+
+    pub fn update(&self, exchange: &str) {
+        self.updates.with_label_values(&[exchange]).inc();
+        self.last_update
+            .lock()
+            .insert(String::from(exchange), Instant::now());
+    }
+
+    pub fn parse_error(&self, exchange: &str) {
+        self.parse_errors.with_label_values(&[exchange]).inc();
+    }
+
+    pub fn violation(&self, exchange: &str, kind: &str) {
+        self.violations.with_label_values(&[exchange, kind]).inc();
+    }
+
+    pub fn quarantined(&self, exchange: &str, quarantined: bool) {
+        self.quarantined
+            .with_label_values(&[exchange])
+            .set(quarantined as i64);
+    }
+
+    pub fn excluded(&self, exchange: &str, excluded: bool) {
+        self.excluded
+            .with_label_values(&[exchange])
+            .set(excluded as i64);
+    }
+
+    pub fn reconnect(&self, exchange: &str) {
+        self.reconnects.with_label_values(&[exchange]).inc();
+    }
+
+    /// Drops the age of an exchange that is no longer connected
+    pub fn forget(&self, exchange: &str) {
+        if self.last_update.lock().remove(exchange).is_some() {
+            self.update_age.remove_label_values(&[exchange]).ok();
+        }
+    }
+
+    pub fn merged(&self, pair: &str, duration: Duration, spread: f64) {
+        self.merge_duration
+            .with_label_values(&[pair])
+            .observe(duration.as_secs_f64());
+        self.spread.with_label_values(&[pair]).set(spread);
+        self.published.inc();
+    }
+
+    /// Records an ended arbitrage window
+    pub fn arbitrage(&self, buy: &str, sell: &str, duration: Duration) {
+        self.arbitrage
+            .with_label_values(&[buy, sell])
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Stops reporting a pair after a reload switched to another one
+    pub fn retire(&self, pair: &str) {
+        self.spread.remove_label_values(&[pair]).ok();
+        self.merge_duration.remove_label_values(&[pair]).ok();
+    }
+
+    /// Records a latency sample in microseconds, `exchange` is `all` once books are merged
+    pub fn latency(&self, stage: &'static str, exchange: &str, micros: u64) {
+        let mut latency = self.latency.lock();
+        let key = (stage, String::from(exchange));
+
+        if !latency.contains_key(&key) {
+            match Histogram::new_with_bounds(1, 60_000_000, 3) {
+                Ok(histogram) => latency.insert(key.clone(), histogram),
+                Err(_) => return,
+            };
+        }
+
+        if let Some(histogram) = latency.get_mut(&key) {
+            histogram.saturating_record(micros.max(1));
+        }
+    }
+
+    /// Counts a streaming client until the guard drops
+    pub fn client(self: &Arc<Self>) -> ClientGuard {
+        self.clients.inc();
+        ClientGuard(Arc::clone(self))
+    }
+
+    pub fn render(&self) -> Result<String> {
+        for (exchange, instant) in self.last_update.lock().iter() {
+            self.update_age
+                .with_label_values(&[exchange])
+                .set(instant.elapsed().as_secs_f64());
+        }
+
+        for ((stage, exchange), histogram) in self.latency.lock().iter() {
+            for quantile in QUANTILES {
+                self.latency_quantiles
+                    .with_label_values(&[stage, exchange, quantile.to_string().as_str()])
+                    .set(histogram.value_at_quantile(quantile) as f64);
+            }
+            self.latency_quantiles
+                .with_label_values(&[stage, exchange, "1"])
+                .set(histogram.max() as f64);
+            self.latency_count
+                .with_label_values(&[stage, exchange])
+                .set(histogram.len() as i64);
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+pub struct ClientGuard(MetricsRef);
+
+impl Drop for ClientGuard {
+    fn drop(&mut self) {
+        self.0.clients.dec();
+    }
+}
+
+/// Binds now and serves `/metrics` over plain HTTP in the background
+pub fn serve(metrics: MetricsRef, bind: SocketAddr) -> Result<JoinHandle<()>> {
+    info!("metrics on {}", bind);
+
+    let service = make_service_fn(move |_| {
+        let metrics = Arc::clone(&metrics);
+
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let metrics = Arc::clone(&metrics);
+                async move { Ok::<_, Infallible>(respond(&metrics, request)) }
+            }))
+        }
+    });
+
+    let server = Server::try_bind(&bind)?.serve(service);
+
+    Ok(tokio::spawn(async move {
+        if let Err(e) = server.await {
+            warn!("metrics server failed - {}", e);
+        }
+    }))
+}
+
+fn respond(metrics: &Metrics, request: Request<Body>) -> Response<Body> {
+    let status = |code: StatusCode| {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = code;
+        response
+    };
+
+    if request.uri().path() != "/metrics" {
+        return status(StatusCode::NOT_FOUND);
+    }
+
+    match metrics.render() {
+        Ok(text) => {
+            let mut response = Response::new(Body::from(text));
+            response.headers_mut().insert(
+                CONTENT_TYPE,
+                TextEncoder::new().format_type().parse().unwrap(),
+            );
+            response
+        }
+        Err(_) => status(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() -> Result<()> {
+        let metrics = Arc::new(Metrics::new()?);
+
+        metrics.update("binance");
+        metrics.parse_error("bitstamp");
+        metrics.violation("bitstamp", "price");
+        metrics.quarantined("bitstamp", true);
+        metrics.merged("ethbtc", Duration::from_micros(20), 0.5);
+        metrics.latency(EVENT_TO_RECEIVE, "binance", 1500);
+        metrics.latency(EVENT_TO_RECEIVE, "binance", 2500);
+        let client = metrics.client();
+
+        let text = metrics.render()?;
+        assert!(text.contains("algo_updates_total{exchange=\"binance\"} 1"));
+        assert!(text.contains("algo_parse_errors_total{exchange=\"bitstamp\"} 1"));
+        assert!(text
+            .contains("algo_validation_violations_total{exchange=\"bitstamp\",kind=\"price\"} 1"));
+        assert!(text.contains("algo_quarantined{exchange=\"bitstamp\"} 1"));
+        assert!(text.contains("algo_update_age_seconds{exchange=\"binance\"}"));
+        assert!(text.contains("algo_spread{pair=\"ethbtc\"} 0.5"));
+        assert!(text.contains("algo_published_total 1"));
+        assert!(text.contains("algo_grpc_clients 1"));
+        assert!(text.contains(
+            "algo_latency_microseconds{exchange=\"binance\",quantile=\"0.5\",stage=\"event_to_receive\"} 1500"
+        ));
+        assert!(text.contains(
+            "algo_latency_microseconds_count{exchange=\"binance\",stage=\"event_to_receive\"} 2"
+        ));
+
+        drop(client);
+        metrics.retire("ethbtc");
+        metrics.forget("binance");
+
+        let text = metrics.render()?;
+        assert!(text.contains("algo_grpc_clients 0"));
+        assert!(!text.contains("algo_spread{"));
+        assert!(!text.contains("algo_update_age_seconds{"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_respond() -> Result<()> {
+        let metrics = Metrics::new()?;
+
+        let request = Request::get("/metrics").body(Body::empty())?;
+        assert_eq!(respond(&metrics, request).status(), StatusCode::OK);
+
+        let request = Request::get("/").body(Body::empty())?;
+        assert_eq!(respond(&metrics, request).status(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+}
@@ -1,3 +1,15 @@
+pub mod admin;
+pub mod arbitrage;
+pub mod breaker;
 pub mod merge;
+pub mod metrics;
 pub mod orderbook;
+pub mod pricing;
 pub mod providers;
+pub mod publisher;
+pub mod quote;
+pub mod reload;
+pub mod shutdown;
+pub mod telemetry;
+pub mod throttle;
+pub mod validate;
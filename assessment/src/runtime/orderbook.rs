@@ -1,47 +1,125 @@
-use super::{merge::merge, providers::Providers};
+use super::{admin::Admin, providers::Providers, publisher::Publisher, quote::quote};
 use anyhow::Result;
 use common::{
-    orderbook::{orderbook_aggregator_server::OrderbookAggregator, Empty, Summary},
+    orderbook::{
+        orderbook_aggregator_server::OrderbookAggregator, Arbitrage, Cost, Empty, Prices,
+        QuoteRequest, Side, Summary,
+    },
     ConfigRef,
 };
-use log::info;
 use std::sync::Arc;
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::{async_trait, Request, Response, Status};
+use tracing::{info, instrument};
 
 pub struct Orderbook {
-    config: ConfigRef,
-    providers: Providers,
+    providers: Arc<Providers>,
+    publisher: Arc<Publisher>,
 }
 
 impl Orderbook {
-    pub fn new(config: ConfigRef) -> Self {
+    pub fn new(config: ConfigRef) -> Result<Self> {
         info!("initialize {}", config.pair());
 
-        let providers = Providers::new(Arc::clone(&config));
+        let providers = Arc::new(Providers::new(config)?);
 
-        Self { config, providers }
+        let publisher = Arc::new(Publisher::new(providers.metrics()));
+
+        Ok(Self {
+            providers,
+            publisher,
+        })
     }
 
+    /// Subscribes the providers and starts publishing the merged book
     pub fn connect(&self) -> Result<()> {
-        self.providers.connect()
+        self.providers.connect()?;
+        self.publisher.spawn(Arc::clone(&self.providers));
+        Ok(())
+    }
+
+    pub fn providers(&self) -> Arc<Providers> {
+        Arc::clone(&self.providers)
+    }
+
+    pub fn publisher(&self) -> Arc<Publisher> {
+        Arc::clone(&self.publisher)
     }
-}
 
-impl Drop for Orderbook {
-    fn drop(&mut self) {
-        self.providers.disconnect().ok();
+    pub fn admin(&self) -> Admin {
+        Admin::new(Arc::clone(&self.providers))
     }
 }
 
 #[async_trait]
 impl OrderbookAggregator for Orderbook {
+    #[instrument(skip_all, fields(pair = self.providers.config().pair()))]
     async fn book_summary(&self, _request: Request<Empty>) -> Result<Response<Summary>, Status> {
-        match self.providers.retrieve().await {
-            Ok(summaries) => {
-                let summary = merge(Arc::clone(&self.config), summaries);
-                Ok(Response::new(summary))
-            }
+        match self.publisher.latest().await {
+            Ok(summary) => Ok(Response::new(self.publisher.outgoing(&summary))),
             Err(e) => Err(Status::internal(e.to_string())),
         }
     }
+
+    type BookSummaryStreamStream = ReceiverStream<Result<Summary, Status>>;
+
+    #[instrument(skip_all, fields(pair = self.providers.config().pair()))]
+    async fn book_summary_stream(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<Self::BookSummaryStreamStream>, Status> {
+        info!("stream");
+
+        Ok(Response::new(self.publisher.stream()))
+    }
+
+    type PriceStreamStream = ReceiverStream<Result<Prices, Status>>;
+
+    #[instrument(skip_all, fields(pair = self.providers.config().pair()))]
+    async fn price_stream(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<Self::PriceStreamStream>, Status> {
+        info!("price stream");
+
+        Ok(Response::new(self.publisher.price_stream()))
+    }
+
+    type ArbitrageStreamStream = ReceiverStream<Result<Arbitrage, Status>>;
+
+    #[instrument(skip_all, fields(pair = self.providers.config().pair()))]
+    async fn arbitrage_stream(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<Self::ArbitrageStreamStream>, Status> {
+        info!("arbitrage stream");
+
+        Ok(Response::new(self.publisher.arbitrage_stream()))
+    }
+
+    #[instrument(skip_all, fields(pair = self.providers.config().pair()))]
+    async fn quote(&self, request: Request<QuoteRequest>) -> Result<Response<Cost>, Status> {
+        let request = request.into_inner();
+        let side =
+            Side::from_i32(request.side).ok_or_else(|| Status::invalid_argument("unknown side"))?;
+        let quantity = request
+            .quantity
+            .ok_or_else(|| Status::invalid_argument("base or quote quantity missing"))?;
+
+        let book = self
+            .publisher
+            .book()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        match quote(
+            &book,
+            side,
+            quantity,
+            self.providers.config().fee_adjusted(),
+        ) {
+            Ok(cost) => Ok(Response::new(cost)),
+            Err(e) => Err(Status::invalid_argument(e.to_string())),
+        }
+    }
 }
@@ -0,0 +1,157 @@
+use super::merge;
+use common::{
+    config::Estimator,
+    orderbook::{Level, Prices, Summary},
+    ConfigRef,
+};
+
+/// Mid-price estimators of a merged full-depth book, the ones configured
+pub fn prices(config: &ConfigRef, book: &Summary) -> Prices {
+    let mut prices = Prices::default();
+
+    // fee-adjusted books are not ranked by price, so look for the best levels
+    let bid = book.bids.iter().max_by(|x, y| x.price.total_cmp(&y.price));
+    let ask = book.asks.iter().min_by(|x, y| x.price.total_cmp(&y.price));
+    let (bid, ask) = match (bid, ask) {
+        (Some(bid), Some(ask)) => (bid, ask),
+        _ => return prices,
+    };
+
+    if config.pricing(Estimator::Mid) {
+        prices.mid = (bid.price + ask.price) / 2.0;
+    }
+    if config.pricing(Estimator::Micro) {
+        prices.micro_price = weighted(bid.price, bid.amount, ask.price, ask.amount);
+    }
+    if config.pricing(Estimator::Depth) {
+        let band = config.pricing_band() / 1e4;
+        let (bid_price, bid_depth) = vwap(&book.bids, |price| price >= bid.price * (1.0 - band));
+        let (ask_price, ask_depth) = vwap(&book.asks, |price| price <= ask.price * (1.0 + band));
+        prices.depth_mid = weighted(bid_price, bid_depth, ask_price, ask_depth);
+    }
+    if config.pricing(Estimator::Exchange) {
+        prices.exchange_mid = exchange_mid(config, book);
+    }
+
+    prices
+}
+
+/// Bid and ask each weighted by the amount across, so the price leans towards the thinner side
+fn weighted(bid: f64, bid_amount: f64, ask: f64, ask_amount: f64) -> f64 {
+    let total = bid_amount + ask_amount;
+    if total <= 0.0 {
+        return (bid + ask) / 2.0;
+    }
+    (bid * ask_amount + ask * bid_amount) / total
+}
+
+/// Average price and total amount of the levels within the band
+fn vwap<F: Fn(f64) -> bool>(levels: &[Level], within: F) -> (f64, f64) {
+    let (notional, amount) = levels
+        .iter()
+        .filter(|level| within(level.price))
+        .fold((0.0, 0.0), |(notional, amount), level| {
+            (notional + level.price * level.amount, amount + level.amount)
+        });
+    (notional / amount, amount)
+}
+
+/// Mids of the exchanges quoting both sides, weighted by the configuration
+fn exchange_mid(config: &ConfigRef, book: &Summary) -> f64 {
+    let (sum, weights) = merge::tops(book)
+        .into_iter()
+        .filter_map(|(exchange, top)| Some((exchange, top.bid?, top.ask?)))
+        .map(|(exchange, bid, ask)| ((bid.price + ask.price) / 2.0, config.weight(exchange)))
+        .fold((0.0, 0.0), |(sum, weights), (mid, weight)| {
+            (sum + mid * weight, weights + weight)
+        });
+
+    match weights > 0.0 {
+        true => sum / weights,
+        false => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use common::config::Config;
+    use std::sync::Arc;
+
+    fn level(exchange: &str, price: f64, amount: f64) -> Level {
+        Level {
+            exchange: String::from(exchange),
+            price,
+            amount,
+            ..Default::default()
+        }
+    }
+
+    fn book() -> Summary {
+        Summary {
+            bids: vec![
+                level("Bitstamp", 100.0, 1.0),
+                level("Binance", 99.95, 3.0),
+                level("Binance", 99.0, 10.0),
+            ],
+            asks: vec![
+                level("Binance", 101.0, 3.0),
+                level("Bitstamp", 101.05, 1.0),
+                level("Bitstamp", 102.0, 10.0),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn close(x: f64, y: f64) -> bool {
+        (x - y).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_estimators() -> Result<()> {
+        let config = Arc::new(Config::load_from(["algo"])?);
+        let prices = prices(&config, &book());
+
+        assert!(close(prices.mid, 100.5));
+        // three across the ask against one across the bid pulls towards the bid
+        assert!(close(prices.micro_price, 100.25));
+
+        // both levels within 10 bps of each best, 4 against 4
+        let bid = (100.0 + 99.95 * 3.0) / 4.0;
+        let ask = (101.0 * 3.0 + 101.05) / 4.0;
+        assert!(close(prices.depth_mid, (bid + ask) / 2.0));
+
+        // binance 99.95 / 101, bitstamp 100 / 101.05
+        assert!(close(prices.exchange_mid, (100.475 + 100.525) / 2.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_configured() -> Result<()> {
+        let config = Config::load_toml(
+            "pricing = [\"exchange\"]\n\n[exchange.binance]\nweight = 3\n",
+            ["algo"],
+        )?;
+        let prices = prices(&Arc::new(config), &book());
+
+        assert_eq!(prices.mid, 0.0);
+        assert_eq!(prices.micro_price, 0.0);
+        assert_eq!(prices.depth_mid, 0.0);
+        assert!(close(prices.exchange_mid, (100.475 * 3.0 + 100.525) / 4.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_one_sided() -> Result<()> {
+        let config = Arc::new(Config::load_from(["algo"])?);
+        let mut book = book();
+        book.asks.clear();
+
+        assert_eq!(prices(&config, &book), Prices::default());
+
+        Ok(())
+    }
+}
@@ -1,72 +1,804 @@
-use anyhow::Result;
-use common::{orderbook::Summary, ConfigRef, Provider};
-use log::info;
-use std::sync::Arc;
-use tokio::task::{self, JoinHandle};
-
-#[cfg(feature = "binance")]
-use binance::Binance;
-#[cfg(feature = "bitstamp")]
-use bitstamp::Bitstamp;
+use super::metrics::{MetricsRef, EVENT_TO_RECEIVE};
+use super::throttle::Throttle;
+use super::validate::Validator;
+use anyhow::{anyhow, Result};
+use common::{
+    clock,
+    config::Config,
+    orderbook::{ProviderState, Summary},
+    registry::Registry,
+    ConfigRef, Provider,
+};
+use parking_lot::{Mutex, RwLock};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::Notify,
+    task::{self, JoinHandle},
+};
+use tracing::{debug, field, info, info_span, warn, Span};
 
 type ProviderRef = Arc<Box<dyn Provider>>;
 
+/// Connection id, unique for the process, and the provider holding it
+type Connection = (u64, ProviderRef);
+
+/// A registered exchange, connected once created and merged while subscribed
+struct Entry {
+    name: String,
+    provider: Option<ProviderRef>,
+    connection: u64,
+    subscribed: bool,
+}
+
+impl Entry {
+    fn state(&self) -> ProviderState {
+        ProviderState {
+            name: self.name.clone(),
+            connected: self.provider.is_some(),
+            subscribed: self.subscribed,
+        }
+    }
+}
+
 pub struct Providers {
-    collection: Vec<ProviderRef>,
+    registry: Registry,
+    config: RwLock<ConfigRef>,
+    entries: RwLock<Vec<Entry>>,
+    metrics: MetricsRef,
+    connections: AtomicU64,
+    throttle: Throttle,
+    validator: Arc<Validator>,
+    /// Woken whenever an entry changes, for the publisher waiting on a subscription
+    changed: Notify,
+    /// Held from reading the running configuration to replacing it
+    reconfiguring: Mutex<()>,
+}
+
+/// Factories of every provider crate compiled in
+#[allow(unused_variables, unused_mut)]
+pub fn registry(config: &ConfigRef) -> Result<Registry> {
+    let mut registry = Registry::default();
+
+    #[cfg(feature = "binance")]
+    binance::register(&mut registry);
+    #[cfg(feature = "bitstamp")]
+    bitstamp::register(&mut registry);
+    #[cfg(feature = "gemini")]
+    gemini::register(&mut registry);
+    #[cfg(feature = "htx")]
+    htx::register(&mut registry);
+    #[cfg(feature = "kucoin")]
+    kucoin::register(&mut registry);
+    #[cfg(feature = "simulator")]
+    simulator::register(&mut registry);
+    // after the built-ins, which definitions may not shadow
+    #[cfg(feature = "generic")]
+    generic::register(&mut registry, config.generic_definitions())?;
+    // captures take the place of the live exchanges they were recorded from
+    #[cfg(feature = "replay")]
+    replay::register(&mut registry, config.replay())?;
+
+    Ok(registry)
+}
+
+/// Validates the configuration against the compiled-in exchanges without connecting
+pub fn check(config: &ConfigRef) -> Result<()> {
+    config.validate()?;
+
+    let registry = registry(config)?;
+
+    for name in config.exchanges() {
+        if !registry.contains(name) {
+            return Err(anyhow!(
+                "unknown exchange {}, available: {}",
+                name,
+                registry.names().collect::<Vec<_>>().join(", ")
+            ));
+        }
+    }
+
+    Ok(())
 }
 
 impl Providers {
-    pub fn new(config: ConfigRef) -> Self {
-        let providers: Vec<ProviderRef> = vec![
-            #[cfg(feature = "binance")]
-            Arc::new(Box::new(Binance::new(Arc::clone(&config)))),
-            #[cfg(feature = "bitstamp")]
-            Arc::new(Box::new(Bitstamp::new(Arc::clone(&config)))),
-        ];
+    pub fn new(config: ConfigRef) -> Result<Self> {
+        let registry = registry(&config)?;
+        Self::with_registry(config, registry)
+    }
+
+    /// Creates the configured exchanges, the others stay registered but disconnected
+    pub fn with_registry(config: ConfigRef, registry: Registry) -> Result<Self> {
+        let mut entries: Vec<Entry> = registry
+            .names()
+            .map(|name| Entry {
+                name: String::from(name),
+                provider: None,
+                connection: 0,
+                subscribed: false,
+            })
+            .collect();
+
+        let metrics: MetricsRef = Arc::default();
+
+        let providers = Self {
+            registry,
+            config: RwLock::new(config),
+            entries: RwLock::new(Vec::new()),
+            validator: Arc::new(Validator::new(Arc::clone(&metrics))),
+            metrics,
+            connections: AtomicU64::new(0),
+            throttle: Throttle::new(Duration::from_secs(10)),
+            changed: Notify::new(),
+            reconfiguring: Mutex::new(()),
+        };
 
-        Self {
-            collection: providers,
+        let config = providers.config();
+        for name in config.exchanges() {
+            let (connection, provider) = providers.create(name, &config)?;
+            let name = name.to_lowercase();
+
+            if let Some(entry) = entries.iter_mut().find(|entry| entry.name == name) {
+                entry.provider = Some(provider);
+                entry.connection = connection;
+            }
         }
+
+        *providers.entries.write() = entries;
+
+        Ok(providers)
+    }
+
+    pub fn config(&self) -> ConfigRef {
+        Arc::clone(&self.config.read())
+    }
+
+    pub fn metrics(&self) -> MetricsRef {
+        Arc::clone(&self.metrics)
     }
 
     pub fn connect(&self) -> Result<()> {
-        info!("connect");
+        info!(event = "connect", "connect");
 
-        for i in 0..self.collection.len() {
-            self.collection[i].subscribe()?;
+        for (name, _) in self.connected() {
+            self.subscribe(name.as_str())?;
         }
 
         Ok(())
     }
 
-    pub async fn retrieve(&self) -> Result<Vec<Summary>> {
-        info!("retrieve");
+    /// Summaries of the subscribed exchanges, those failing to give one are left out
+    pub async fn retrieve(&self) -> Vec<Summary> {
+        debug!(event = "retrieve", "retrieve");
+
+        let config = self.config();
+        let subscribed: Vec<(String, u64, Option<usize>, ProviderRef)> = self
+            .entries
+            .read()
+            .iter()
+            .filter(|entry| entry.subscribed)
+            .filter_map(|entry| {
+                let provider = entry.provider.clone()?;
+                Some((
+                    entry.name.clone(),
+                    entry.connection,
+                    config.depth(&entry.name),
+                    provider,
+                ))
+            })
+            .collect();
+
+        let count = subscribed.len();
+
+        let mut handles = Vec::<(String, JoinHandle<Option<Summary>>)>::with_capacity(count);
+
+        for (name, connection, depth, provider) in subscribed {
+            let metrics = Arc::clone(&self.metrics);
+            let throttle = self.throttle.clone();
+            let validator = Arc::clone(&self.validator);
+            let policy = config.validation(&name);
+            let quarantine = config.quarantine();
+            let span = info_span!(
+                "summary",
+                exchange = name.as_str(),
+                pair = config.pair(),
+                connection,
+                bids = field::Empty,
+                asks = field::Empty,
+                error = field::Empty,
+            );
 
-        let count = self.collection.len();
+            let exchange = name.clone();
+            handles.push((
+                exchange,
+                task::spawn_blocking(move || {
+                    let _entered = span.enter();
 
-        let mut handles = Vec::<JoinHandle<Result<Summary>>>::with_capacity(count);
+                    let mut summary = match provider.summary() {
+                        Ok(summary) => summary,
+                        Err(e) => {
+                            span.record("error", field::display(&e));
+                            metrics.parse_error(&name);
 
-        for i in 0..self.collection.len() {
-            let provider = Arc::clone(&self.collection[i]);
-            handles.push(task::spawn_blocking(move || provider.summary()));
+                            let key = format!("{} {}", name, e);
+                            if let Some(suppressed) = throttle.allow(&key) {
+                                warn!(
+                                    event = "summary_error",
+                                    suppressed, "summary failed - {}", e
+                                );
+                            }
+
+                            // a failed exchange sits this merge out, the others are still published
+                            return None;
+                        }
+                    };
+                    span.record("bids", summary.bids.len());
+                    span.record("asks", summary.asks.len());
+                    metrics.update(&name);
+
+                    // providers without a receipt timestamp are stamped once parsed
+                    if summary.receive_timestamp == 0 {
+                        summary.receive_timestamp = clock::micros();
+                    }
+                    if let Some(micros) =
+                        clock::elapsed(summary.event_timestamp, summary.receive_timestamp)
+                    {
+                        metrics.latency(EVENT_TO_RECEIVE, &name, micros);
+                    }
+
+                    // a dropped book leaves the exchange out of this merge only
+                    let mut summary = match validator.validate(&name, policy, quarantine, summary) {
+                        Some(summary) => summary,
+                        None => return None,
+                    };
+
+                    if let Some(depth) = depth {
+                        summary.asks.truncate(depth);
+                        summary.bids.truncate(depth);
+                    }
+
+                    Some(summary)
+                }),
+            ));
         }
 
         let mut summaries = Vec::<Summary>::with_capacity(count);
 
-        for handle in handles {
-            summaries.push(handle.await??);
+        for (name, handle) in handles {
+            match handle.await {
+                Ok(summary) => summaries.extend(summary),
+                Err(e) => {
+                    self.metrics.parse_error(&name);
+
+                    let key = format!("{} {}", name, e);
+                    if let Some(suppressed) = self.throttle.allow(&key) {
+                        warn!(
+                            exchange = name.as_str(),
+                            event = "summary_error",
+                            suppressed,
+                            "summary panicked - {}",
+                            e
+                        );
+                    }
+                }
+            }
         }
 
-        Ok(summaries)
+        summaries
+    }
+
+    /// Whether no connected exchange is subscribed, leaving nothing to retrieve
+    pub fn is_idle(&self) -> bool {
+        !self
+            .entries
+            .read()
+            .iter()
+            .any(|entry| entry.subscribed && entry.provider.is_some())
+    }
+
+    /// Returns once an exchange is subscribed
+    pub async fn until_subscribed(&self) {
+        loop {
+            // registered before the check, so a change in between still wakes it
+            let changed = self.changed.notified();
+            if !self.is_idle() {
+                return;
+            }
+            changed.await;
+        }
     }
 
     pub fn disconnect(&self) -> Result<()> {
-        info!("disconnect");
+        info!(event = "disconnect", "disconnect");
 
-        for i in 0..self.collection.len() {
-            self.collection[i].unsubscribe()?;
+        for (name, subscribed) in self.connected() {
+            if subscribed {
+                self.unsubscribe(name.as_str())?;
+            }
         }
 
         Ok(())
     }
+
+    /// Unsubscribes and closes every connection, going on past failures
+    pub fn shutdown(&self) -> Result<()> {
+        info!(event = "shutdown", "shutdown");
+
+        let mut result = Ok(());
+
+        for (name, _) in self.connected() {
+            if let Err(e) = self.close(name.as_str()) {
+                warn!(
+                    exchange = name.as_str(),
+                    event = "close",
+                    "close failed - {}",
+                    e
+                );
+                result = result.and(Err(e));
+            }
+        }
+
+        result
+    }
+
+    pub fn states(&self) -> Vec<ProviderState> {
+        self.entries.read().iter().map(Entry::state).collect()
+    }
+
+    /// Connects the exchange when needed and merges it from now on
+    pub fn subscribe(&self, name: &str) -> Result<ProviderState> {
+        self.subscribe_with(name, &self.config())
+    }
+
+    fn subscribe_with(&self, name: &str, config: &ConfigRef) -> Result<ProviderState> {
+        let provider = match self.provider(name)? {
+            Some(provider) => provider,
+            None => self.replace(name, self.create(name, config)?)?,
+        };
+
+        let _entered = self.span(name).entered();
+        info!(event = "subscribe", "subscribe");
+
+        provider.subscribe()?;
+
+        self.update(name, |entry| entry.subscribed = true)
+    }
+
+    /// Stops merging the exchange, the connection is kept for a later subscribe
+    pub fn unsubscribe(&self, name: &str) -> Result<ProviderState> {
+        let provider = self
+            .provider(name)?
+            .ok_or_else(|| anyhow!("{} is not connected", name))?;
+
+        let _entered = self.span(name).entered();
+        info!(event = "unsubscribe", "unsubscribe");
+
+        provider.unsubscribe()?;
+
+        self.update(name, |entry| entry.subscribed = false)
+    }
+
+    /// Replaces the connection with a new one, subscribed again if it was
+    pub fn reconnect(&self, name: &str) -> Result<ProviderState> {
+        self.reconnect_with(name, &self.config())
+    }
+
+    fn reconnect_with(&self, name: &str, config: &ConfigRef) -> Result<ProviderState> {
+        let span = self.span(name);
+        let _entered = span.enter();
+        info!(event = "reconnect", "reconnect");
+        self.metrics.reconnect(&name.to_lowercase());
+
+        let subscribed = self.state(name)?.subscribed;
+        let (connection, provider) = self.create(name, config)?;
+        span.record("connection", connection);
+
+        if subscribed {
+            provider.subscribe()?;
+        }
+
+        self.replace(name, (connection, provider))?;
+        self.state(name)
+    }
+
+    /// Unsubscribes and closes the connection
+    pub fn close(&self, name: &str) -> Result<ProviderState> {
+        if self.state(name)?.subscribed {
+            self.unsubscribe(name)?;
+        }
+
+        let _entered = self.span(name).entered();
+        info!(event = "close", "close");
+
+        self.metrics.forget(&name.to_lowercase());
+        self.update(name, |entry| entry.provider = None)
+    }
+
+    /// Applies the configuration `change` makes of the running one, touching only the exchanges it
+    /// affects, the rest keep streaming
+    ///
+    /// An exchange failing does not stop the others, but the running configuration is only replaced
+    /// once every one applied, so the next reconfigure tries the failed ones again. Reconfigures
+    /// run one at a time, none starts from a configuration another is replacing.
+    pub fn reconfigure<F>(&self, change: F) -> Result<Vec<ProviderState>>
+    where
+        F: FnOnce(&Config) -> Result<Config>,
+    {
+        let _reconfiguring = self.reconfiguring.lock();
+
+        let previous = self.config();
+        let config = Arc::new(change(&previous)?);
+
+        if previous.generic_definitions() != config.generic_definitions()
+            || previous.replay() != config.replay()
+        {
+            warn!("generic definitions and replays are only loaded on start");
+        }
+        if previous.local_bind() != config.local_bind()
+            || previous.metrics_bind() != config.metrics_bind()
+        {
+            warn!("binds are only applied on start");
+        }
+
+        let listed = |config: &Config, name: &str| {
+            config
+                .exchanges()
+                .iter()
+                .any(|exchange| exchange.to_lowercase() == name)
+        };
+
+        let names: Vec<String> = self.registry.names().map(String::from).collect();
+        let mut failed = Vec::new();
+
+        for name in names.iter().map(String::as_str) {
+            let state = self.state(name)?;
+
+            let applied = match (listed(&previous, name), listed(&config, name)) {
+                (true, false) if state.connected => self.close(name).map(drop),
+                (false, true) if !state.subscribed => self.subscribe_with(name, &config).map(drop),
+                _ if state.connected
+                    && (previous.pair() != config.pair()
+                        || previous.url(name) != config.url(name)
+                        || previous.record(name) != config.record(name)
+                        || previous.chaos(name) != config.chaos(name)
+                        || (config.chaos(name) && previous.faults() != config.faults())
+                        || previous.depth(name) != config.depth(name)
+                        || previous.update_speed(name) != config.update_speed(name)
+                        || (name == "simulator"
+                            && previous.simulation() != config.simulation())) =>
+                {
+                    self.reconnect_with(name, &config).map(drop)
+                }
+                _ => Ok(()),
+            };
+
+            if let Err(e) = applied {
+                warn!(
+                    exchange = name,
+                    event = "reconfigure",
+                    "reconfigure failed - {}",
+                    e
+                );
+                failed.push(name);
+            }
+        }
+
+        if !failed.is_empty() {
+            return Err(anyhow!("reconfigure failed for {}", failed.join(", ")));
+        }
+
+        *self.config.write() = config;
+        Ok(self.states())
+    }
+
+    fn connected(&self) -> Vec<(String, bool)> {
+        self.entries
+            .read()
+            .iter()
+            .filter(|entry| entry.provider.is_some())
+            .map(|entry| (entry.name.clone(), entry.subscribed))
+            .collect()
+    }
+
+    fn create(&self, name: &str, config: &ConfigRef) -> Result<Connection> {
+        let connection = self.connections.fetch_add(1, Ordering::Relaxed) + 1;
+        info!(
+            exchange = name.to_lowercase().as_str(),
+            connection,
+            event = "create",
+            "create"
+        );
+
+        // factories inject the faults themselves, into the transport or else the summaries
+        if config.chaos(name) {
+            warn!(
+                exchange = name.to_lowercase().as_str(),
+                event = "chaos",
+                "injecting faults"
+            );
+        }
+        let provider = self.registry.create(name, Arc::clone(config), connection)?;
+
+        Ok((connection, Arc::new(provider)))
+    }
+
+    /// Context of every log line about the exchange, provider crates included
+    fn span(&self, name: &str) -> Span {
+        let connection = self.find(name, |entry| entry.connection).unwrap_or(0);
+
+        info_span!(
+            "provider",
+            exchange = name.to_lowercase().as_str(),
+            pair = self.config().pair(),
+            connection,
+        )
+    }
+
+    fn provider(&self, name: &str) -> Result<Option<ProviderRef>> {
+        self.find(name, |entry| entry.provider.clone())
+    }
+
+    fn state(&self, name: &str) -> Result<ProviderState> {
+        self.find(name, Entry::state)
+    }
+
+    fn find<T, F>(&self, name: &str, read: F) -> Result<T>
+    where
+        F: FnOnce(&Entry) -> T,
+    {
+        let name = name.to_lowercase();
+
+        self.entries
+            .read()
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(read)
+            .ok_or_else(|| anyhow!("unknown exchange {}", name))
+    }
+
+    /// Swaps the connection, the previous one closes once in-flight reads finish
+    fn replace(&self, name: &str, (connection, provider): Connection) -> Result<ProviderRef> {
+        self.update(name, |entry| {
+            entry.provider = Some(Arc::clone(&provider));
+            entry.connection = connection;
+        })?;
+        Ok(provider)
+    }
+
+    fn update<F>(&self, name: &str, change: F) -> Result<ProviderState>
+    where
+        F: FnOnce(&mut Entry),
+    {
+        let name = name.to_lowercase();
+        let mut entries = self.entries.write();
+
+        let entry = entries
+            .iter_mut()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| anyhow!("unknown exchange {}", name))?;
+
+        change(entry);
+        let state = entry.state();
+        drop(entries);
+
+        self.changed.notify_waiters();
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::chaos;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Fake(ConfigRef);
+
+    impl Provider for Fake {
+        fn name(&self) -> &'static str {
+            "Fake"
+        }
+        fn subscribe(&self) -> Result<()> {
+            Ok(())
+        }
+        fn unsubscribe(&self) -> Result<()> {
+            Ok(())
+        }
+        fn summary(&self) -> Result<Summary> {
+            Ok(Summary {
+                spread: self.0.top() as f64,
+                ..Default::default()
+            })
+        }
+    }
+
+    fn providers(created: &Arc<AtomicUsize>) -> Result<Providers> {
+        let mut registry = Registry::default();
+
+        for name in ["binance", "bitstamp", "gemini"] {
+            let created = Arc::clone(created);
+            registry.register(name, move |config, _| {
+                created.fetch_add(1, Ordering::SeqCst);
+                Ok(Box::new(Fake(config)))
+            });
+        }
+
+        Providers::with_registry(Config::as_ref(), registry)
+    }
+
+    #[tokio::test]
+    async fn test_faulted_exchange() -> Result<()> {
+        let mut registry = Registry::default();
+        for name in ["binance", "bitstamp"] {
+            registry.register(name, move |config, _| {
+                Ok(chaos::feed(
+                    &config,
+                    name,
+                    Box::new(Fake(Arc::clone(&config))),
+                ))
+            });
+        }
+        let config = Config::load_from(["algo", "--chaos", "binance", "--chaos-disconnect", "1"])?;
+
+        let providers = Providers::with_registry(Arc::new(config), registry)?;
+        providers.connect()?;
+
+        // the disconnected exchange sits out, the merge keeps the other
+        for _ in 0..3 {
+            assert_eq!(providers.retrieve().await.len(), 1);
+        }
+        assert_eq!(providers.state("binance")?, state("binance", true, true));
+
+        Ok(())
+    }
+
+    fn state(name: &str, connected: bool, subscribed: bool) -> ProviderState {
+        ProviderState {
+            name: String::from(name),
+            connected,
+            subscribed,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_and_unsubscribe() -> Result<()> {
+        let providers = providers(&Arc::default())?;
+
+        assert_eq!(
+            providers.states(),
+            vec![
+                state("binance", true, false),
+                state("bitstamp", true, false),
+                state("gemini", false, false),
+            ]
+        );
+
+        providers.connect()?;
+        assert_eq!(providers.retrieve().await.len(), 2);
+
+        assert_eq!(providers.subscribe("Gemini")?, state("gemini", true, true));
+        assert_eq!(providers.retrieve().await.len(), 3);
+
+        assert_eq!(
+            providers.unsubscribe("binance")?,
+            state("binance", true, false)
+        );
+        assert_eq!(providers.retrieve().await.len(), 2);
+
+        assert!(providers.subscribe("kraken").is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_and_reconfigure() -> Result<()> {
+        let created = Arc::new(AtomicUsize::new(0));
+        let providers = providers(&created)?;
+        providers.connect()?;
+        assert_eq!(created.load(Ordering::SeqCst), 2);
+
+        assert_eq!(
+            providers.reconnect("bitstamp")?,
+            state("bitstamp", true, true)
+        );
+        assert_eq!(created.load(Ordering::SeqCst), 3);
+
+        providers.reconfigure(|config| Ok(config.with_top(3)))?;
+        assert_eq!(created.load(Ordering::SeqCst), 3);
+
+        providers.reconfigure(|config| Ok(config.with_pair("ETHUSDT")))?;
+        assert_eq!(created.load(Ordering::SeqCst), 5);
+        assert_eq!(providers.config().pair(), "ethusdt");
+
+        for summary in providers.retrieve().await {
+            assert_eq!(summary.spread, 3.0);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reconfigure_exchanges() -> Result<()> {
+        let created = Arc::new(AtomicUsize::new(0));
+        let providers = providers(&created)?;
+        providers.connect()?;
+
+        let states = providers
+            .reconfigure(|_| Config::load_from(["algo", "--exchanges", "bitstamp,gemini"]))?;
+
+        assert_eq!(
+            states,
+            vec![
+                state("binance", false, false),
+                state("bitstamp", true, true),
+                state("gemini", true, true),
+            ]
+        );
+        assert_eq!(created.load(Ordering::SeqCst), 3);
+        assert_eq!(providers.retrieve().await.len(), 2);
+
+        // merging settings leave the connection alone
+        providers.reconfigure(|_| {
+            Config::load_toml(
+                "exchanges = [\"bitstamp\", \"gemini\"]\n\n[exchange.gemini]\nweight = 2\n",
+                ["algo"],
+            )
+        })?;
+        assert_eq!(created.load(Ordering::SeqCst), 3);
+        assert_eq!(providers.config().weight("gemini"), 2.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reconfigure_failure() -> Result<()> {
+        let mut registry = Registry::default();
+        registry.register("binance", |config, _| Ok(Box::new(Fake(config))));
+        registry.register("bitstamp", |config, _| match config.pair() {
+            "xrpbtc" => Err(anyhow!("no such market")),
+            _ => Ok(Box::new(Fake(config))),
+        });
+        let providers = Providers::with_registry(Config::as_ref(), registry)?;
+        providers.connect()?;
+
+        let error = providers
+            .reconfigure(|config| Ok(config.with_pair("XRPBTC")))
+            .unwrap_err();
+        assert_eq!(error.to_string(), "reconfigure failed for bitstamp");
+
+        // binance went on to the new pair, the running configuration is kept to try again
+        assert_eq!(providers.config().pair(), "ethbtc");
+        assert_eq!(providers.find("binance", |entry| entry.connection)?, 3);
+        assert_eq!(
+            providers.states(),
+            vec![state("binance", true, true), state("bitstamp", true, true)]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shutdown() -> Result<()> {
+        let providers = providers(&Arc::default())?;
+        providers.connect()?;
+
+        providers.shutdown()?;
+
+        assert_eq!(
+            providers.states(),
+            vec![
+                state("binance", false, false),
+                state("bitstamp", false, false),
+                state("gemini", false, false),
+            ]
+        );
+        assert!(providers.retrieve().await.is_empty());
+
+        Ok(())
+    }
 }
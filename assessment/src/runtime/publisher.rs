@@ -0,0 +1,466 @@
+use super::{
+    arbitrage::Detector,
+    breaker::Breaker,
+    merge,
+    metrics::{MetricsRef, MERGE_TO_SEND, RECEIVE_TO_MERGE},
+    pricing,
+    providers::Providers,
+};
+use anyhow::{anyhow, Result};
+use common::{
+    clock,
+    orderbook::{Arbitrage, Phase, Prices, Summary},
+};
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc, watch,
+    },
+    task::JoinHandle,
+    time,
+};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::Status;
+use tracing::{info, warn};
+
+pub type SummaryRef = Arc<Summary>;
+pub type PricesRef = Arc<Prices>;
+
+#[derive(Default)]
+struct Windows {
+    ended: VecDeque<Arbitrage>,
+    /// Latest event of each open window, keyed by the exchanges to buy from and sell to
+    open: BTreeMap<(String, String), Arbitrage>,
+}
+
+/// Merges the providers in a loop and hands the latest book to every client
+pub struct Publisher {
+    sender: watch::Sender<Option<SummaryRef>>,
+    /// Full depth of the latest merge, for quotes
+    books: watch::Sender<Option<SummaryRef>>,
+    prices: watch::Sender<Option<PricesRef>>,
+    arbitrage: broadcast::Sender<Arbitrage>,
+    /// Arbitrage windows a new client starts from, locked while sending so it sees each event once
+    windows: Mutex<Windows>,
+    closing: watch::Sender<bool>,
+    metrics: MetricsRef,
+    timestamps: AtomicBool,
+}
+
+impl Publisher {
+    pub fn new(metrics: MetricsRef) -> Self {
+        let (sender, _) = watch::channel(None);
+        let (books, _) = watch::channel(None);
+        let (prices, _) = watch::channel(None);
+        let (arbitrage, _) = broadcast::channel(1024);
+        let (closing, _) = watch::channel(false);
+        Self {
+            sender,
+            books,
+            prices,
+            arbitrage,
+            windows: Mutex::default(),
+            closing,
+            metrics,
+            timestamps: AtomicBool::new(false),
+        }
+    }
+
+    pub fn spawn(self: &Arc<Self>, providers: Arc<Providers>) -> JoinHandle<()> {
+        let publisher = Arc::clone(self);
+
+        tokio::spawn(async move {
+            let mut pair = providers.config().pair().to_owned();
+            let mut breaker = Breaker::new(Arc::clone(&publisher.metrics));
+            let mut detector = Detector::new(Arc::clone(&publisher.metrics));
+
+            let mut closing = publisher.closing.subscribe();
+
+            while !*closing.borrow_and_update() {
+                // nothing to merge, retrieving would return at once and spin
+                if providers.is_idle() {
+                    tokio::select! {
+                        _ = providers.until_subscribed() => {}
+                        _ = closing.changed() => {}
+                    }
+                    continue;
+                }
+
+                let summaries = providers.retrieve().await;
+                // nothing came back, broken connections fail at once so wait instead of spinning
+                let failed = summaries.is_empty();
+
+                let config = providers.config();
+                publisher
+                    .timestamps
+                    .store(config.summary_timestamps(), Ordering::Relaxed);
+
+                if config.pair() != pair {
+                    publisher.metrics.retire(&pair);
+                    pair = config.pair().to_owned();
+                }
+
+                let (summaries, excluded) = breaker.filter(
+                    summaries,
+                    config.breaker_threshold(),
+                    config.breaker_window(),
+                );
+
+                let start = Instant::now();
+                let book = merge::book(&config, summaries);
+                let mut summary = merge::top(&config, &book);
+                summary.excluded = excluded;
+                publisher
+                    .metrics
+                    .merged(&pair, start.elapsed(), summary.spread);
+
+                if let Some(micros) =
+                    clock::elapsed(summary.receive_timestamp, summary.merge_timestamp)
+                {
+                    publisher.metrics.latency(RECEIVE_TO_MERGE, "all", micros);
+                }
+
+                let prices = pricing::prices(&config, &book);
+                for event in detector.detect(&config, &book) {
+                    publisher.announce(event, config.arbitrage_history());
+                }
+                publisher.books.send_replace(Some(Arc::new(book)));
+                publisher.prices.send_replace(Some(Arc::new(prices)));
+                publisher.sender.send_replace(Some(Arc::new(summary)));
+
+                if failed {
+                    time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        })
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Option<SummaryRef>> {
+        self.sender.subscribe()
+    }
+
+    /// Latest merged book, waiting for the first one after start
+    pub async fn latest(&self) -> Result<SummaryRef> {
+        first(self.subscribe()).await
+    }
+
+    /// Every level of the latest merge, waiting for the first one after start
+    pub async fn book(&self) -> Result<SummaryRef> {
+        first(self.books.subscribe()).await
+    }
+
+    /// Client stream of merged books, ended with an `unavailable` status on close
+    pub fn stream(self: &Arc<Self>) -> ReceiverStream<Result<Summary, Status>> {
+        let publisher = Arc::clone(self);
+        self.forward(self.subscribe(), move |summary| publisher.outgoing(summary))
+    }
+
+    /// Client stream of the mid-price estimators, one per merge
+    pub fn price_stream(&self) -> ReceiverStream<Result<Prices, Status>> {
+        self.forward(self.prices.subscribe(), Prices::clone)
+    }
+
+    /// Client stream of arbitrage events, the last ended windows and the open ones first
+    pub fn arbitrage_stream(&self) -> ReceiverStream<Result<Arbitrage, Status>> {
+        let (sender, receiver) = mpsc::channel(64);
+        let mut closing = self.closing.subscribe();
+        let client = self.metrics.client();
+
+        let (mut events, replay) = {
+            let windows = self.windows.lock();
+            let replay: Vec<Arbitrage> = windows
+                .ended
+                .iter()
+                .chain(windows.open.values())
+                .cloned()
+                .collect();
+            (self.arbitrage.subscribe(), replay)
+        };
+
+        tokio::spawn(async move {
+            let _client = client;
+
+            for event in replay {
+                if sender.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
+
+            loop {
+                if *closing.borrow_and_update() {
+                    let status = Status::unavailable("server shutting down");
+                    sender.send(Err(status)).await.ok();
+                    break;
+                }
+
+                tokio::select! {
+                    event = events.recv() => match event {
+                        Ok(event) => {
+                            if sender.send(Ok(event)).await.is_err() {
+                                break;
+                            }
+                        }
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!(event = "arbitrage_lagged", skipped, "client fell behind");
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                    changed = closing.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+
+        receiver.into()
+    }
+
+    /// Sends an arbitrage event to the clients, keeping the open and the last ended windows
+    fn announce(&self, event: Arbitrage, keep: usize) {
+        let mut windows = self.windows.lock();
+
+        let key = (event.buy.clone(), event.sell.clone());
+        if event.phase == Phase::End as i32 {
+            windows.open.remove(&key);
+            windows.ended.push_back(event.clone());
+        } else {
+            windows.open.insert(key, event.clone());
+        }
+        while windows.ended.len() > keep {
+            windows.ended.pop_front();
+        }
+
+        // no client listening is not an error
+        self.arbitrage.send(event).ok();
+    }
+
+    /// Sends a client every value of the channel, converted, until close
+    fn forward<T, U, F>(
+        &self,
+        mut values: watch::Receiver<Option<Arc<T>>>,
+        convert: F,
+    ) -> ReceiverStream<Result<U, Status>>
+    where
+        T: Send + Sync + 'static,
+        U: Send + 'static,
+        F: Fn(&T) -> U + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(1);
+        let mut closing = self.closing.subscribe();
+        let client = self.metrics.client();
+
+        tokio::spawn(async move {
+            let _client = client;
+            let mut latest = values.borrow_and_update().clone();
+
+            loop {
+                if *closing.borrow_and_update() {
+                    let status = Status::unavailable("server shutting down");
+                    sender.send(Err(status)).await.ok();
+                    break;
+                }
+
+                if let Some(value) = latest.take() {
+                    if sender.send(Ok(convert(&value))).await.is_err() {
+                        break;
+                    }
+                }
+
+                tokio::select! {
+                    changed = values.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        latest = values.borrow_and_update().clone();
+                    }
+                    changed = closing.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+
+        receiver.into()
+    }
+
+    /// Copy sent to a client, stamped and measured or stripped of the timestamps
+    pub fn outgoing(&self, summary: &Summary) -> Summary {
+        let mut summary = summary.clone();
+        summary.send_timestamp = clock::micros();
+
+        if let Some(micros) = clock::elapsed(summary.merge_timestamp, summary.send_timestamp) {
+            self.metrics.latency(MERGE_TO_SEND, "all", micros);
+        }
+
+        if !self.timestamps.load(Ordering::Relaxed) {
+            summary.event_timestamp = 0;
+            summary.receive_timestamp = 0;
+            summary.merge_timestamp = 0;
+            summary.send_timestamp = 0;
+        }
+
+        summary
+    }
+
+    /// Stops merging and ends every client stream
+    pub fn close(&self) {
+        info!(event = "close", "close publisher");
+        self.closing.send_replace(true);
+    }
+}
+
+/// Current value of the channel, or the first one sent
+async fn first(mut receiver: watch::Receiver<Option<SummaryRef>>) -> Result<SummaryRef> {
+    loop {
+        if let Some(summary) = receiver.borrow_and_update().as_ref() {
+            return Ok(Arc::clone(summary));
+        }
+
+        receiver
+            .changed()
+            .await
+            .map_err(|_| anyhow!("publisher stopped"))?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{config::Config, registry::Registry, Provider};
+    use tokio::time::timeout;
+    use tokio_stream::StreamExt;
+    use tonic::Code;
+
+    struct Fake;
+
+    impl Provider for Fake {
+        fn name(&self) -> &'static str {
+            "Fake"
+        }
+        fn subscribe(&self) -> Result<()> {
+            Ok(())
+        }
+        fn unsubscribe(&self) -> Result<()> {
+            Ok(())
+        }
+        fn summary(&self) -> Result<Summary> {
+            std::thread::sleep(Duration::from_millis(1));
+            Ok(Summary::default())
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_idle_without_subscription() -> Result<()> {
+        let mut registry = Registry::default();
+        registry.register("binance", |_, _| Ok(Box::new(Fake)));
+        let providers = Arc::new(Providers::with_registry(
+            Arc::new(Config::load_from(["algo", "--exchanges", "binance"])?),
+            registry,
+        )?);
+        providers.connect()?;
+
+        let publisher = Arc::new(Publisher::new(Arc::default()));
+        let handle = publisher.spawn(Arc::clone(&providers));
+        let mut summaries = publisher.subscribe();
+        publisher.latest().await?;
+
+        providers.unsubscribe("binance")?;
+        time::sleep(Duration::from_millis(50)).await;
+
+        // nothing merged while idle
+        summaries.borrow_and_update();
+        time::sleep(Duration::from_millis(100)).await;
+        assert!(!summaries.has_changed()?);
+
+        // and back to merging on the next subscription
+        providers.subscribe("binance")?;
+        timeout(Duration::from_secs(1), summaries.changed()).await??;
+
+        providers.unsubscribe("binance")?;
+        publisher.close();
+        timeout(Duration::from_secs(1), handle).await??;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_ends_on_close() -> Result<()> {
+        let publisher = Arc::new(Publisher::new(Arc::default()));
+        let summary = Summary {
+            spread: 1.0,
+            merge_timestamp: clock::micros(),
+            ..Default::default()
+        };
+        publisher
+            .sender
+            .send_replace(Some(Arc::new(summary.clone())));
+
+        let mut stream = publisher.stream();
+        match stream.next().await {
+            Some(Ok(first)) => {
+                assert_eq!(first.spread, summary.spread);
+                assert_eq!(first.merge_timestamp, 0);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(publisher.latest().await?.spread, 1.0);
+
+        publisher.close();
+
+        match stream.next().await {
+            Some(Err(status)) => assert_eq!(status.code(), Code::Unavailable),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(stream.next().await.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_arbitrage_history() -> Result<()> {
+        let publisher = Publisher::new(Arc::default());
+        let event = |phase: Phase, buy: &str| Arbitrage {
+            phase: phase as i32,
+            buy: String::from(buy),
+            ..Default::default()
+        };
+
+        publisher.announce(event(Phase::End, "first"), 1);
+        publisher.announce(event(Phase::End, "second"), 1);
+        publisher.announce(event(Phase::Start, "third"), 1);
+        publisher.announce(event(Phase::Start, "open"), 1);
+        publisher.announce(event(Phase::End, "third"), 1);
+
+        let mut stream = publisher.arbitrage_stream();
+        publisher.announce(event(Phase::Start, "fourth"), 1);
+
+        // only the last ended window is kept, then the open ones, then the live events follow
+        for buy in ["third", "open", "fourth"] {
+            match stream.next().await {
+                Some(Ok(arbitrage)) => assert_eq!(arbitrage.buy, buy),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+
+        publisher.close();
+        match stream.next().await {
+            Some(Err(status)) => assert_eq!(status.code(), Code::Unavailable),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use anyhow::{bail, Result};
+use common::orderbook::{quote_request::Quantity, Cost, Fill, Side, Summary};
+use std::collections::BTreeMap;
+
+/// Cost of filling the quantity against every level of a merged book, best first
+pub fn quote(book: &Summary, side: Side, quantity: Quantity, fee_adjusted: bool) -> Result<Cost> {
+    let target = match quantity {
+        Quantity::Base(amount) | Quantity::Quote(amount) => amount,
+    };
+    if !target.is_finite() || target <= 0.0 {
+        bail!("quantity must be positive");
+    }
+
+    let levels = match side {
+        Side::Buy => &book.asks,
+        Side::Sell => &book.bids,
+    };
+
+    let mut cost = Cost::default();
+    let mut effective = 0.0;
+    let mut fills = BTreeMap::<&str, Fill>::new();
+    let mut remaining = target;
+
+    for level in levels {
+        if remaining <= 0.0 {
+            break;
+        }
+
+        let wanted = match quantity {
+            Quantity::Base(_) => remaining,
+            Quantity::Quote(_) => remaining / level.price,
+        };
+        let base = if level.amount >= wanted {
+            remaining = 0.0;
+            wanted
+        } else {
+            remaining -= match quantity {
+                Quantity::Base(_) => level.amount,
+                Quantity::Quote(_) => level.amount * level.price,
+            };
+            level.amount
+        };
+
+        cost.base += base;
+        cost.quote += base * level.price;
+        // fee-adjusted books are ordered by effective price, the last level is not always the worst
+        cost.worst_price = match (side, cost.base > base) {
+            (_, false) => level.price,
+            (Side::Buy, true) => cost.worst_price.max(level.price),
+            (Side::Sell, true) => cost.worst_price.min(level.price),
+        };
+        effective += base * level.effective_price;
+
+        let fill = fills.entry(&level.exchange).or_insert_with(|| Fill {
+            exchange: level.exchange.clone(),
+            ..Default::default()
+        });
+        fill.base += base;
+        fill.quote += base * level.price;
+    }
+
+    cost.insufficient = remaining > 0.0;
+    cost.fills = fills.into_values().collect();
+
+    if cost.base > 0.0 {
+        cost.vwap = cost.quote / cost.base;
+        if fee_adjusted {
+            cost.effective_vwap = effective / cost.base;
+        }
+    }
+
+    if let (Some(bid), Some(ask)) = (book.bids.first(), book.asks.first()) {
+        cost.mid = (bid.price + ask.price) / 2.0;
+    }
+
+    if cost.mid > 0.0 && cost.base > 0.0 {
+        let away = match side {
+            Side::Buy => cost.vwap - cost.mid,
+            Side::Sell => cost.mid - cost.vwap,
+        };
+        cost.slippage = away / cost.mid * 1e4;
+    }
+
+    Ok(cost)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::orderbook::Level;
+
+    fn level(exchange: &str, price: f64, amount: f64) -> Level {
+        Level {
+            exchange: String::from(exchange),
+            price,
+            amount,
+            effective_price: price * 1.001,
+        }
+    }
+
+    fn book() -> Summary {
+        Summary {
+            bids: vec![level("Bitstamp", 99.0, 1.0), level("Binance", 98.0, 2.0)],
+            asks: vec![
+                level("Binance", 101.0, 1.0),
+                level("Bitstamp", 102.0, 2.0),
+                level("Binance", 103.0, 3.0),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn close(x: f64, y: f64) -> bool {
+        (x - y).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_buy_base() -> Result<()> {
+        let cost = quote(&book(), Side::Buy, Quantity::Base(4.0), false)?;
+
+        // 1 at 101, 2 at 102 and 1 at 103
+        assert!(close(cost.base, 4.0));
+        assert!(close(cost.quote, 408.0));
+        assert!(close(cost.vwap, 102.0));
+        assert_eq!(cost.worst_price, 103.0);
+        assert_eq!(cost.mid, 100.0);
+        assert!(close(cost.slippage, 200.0));
+        assert!(!cost.insufficient);
+        assert_eq!(cost.effective_vwap, 0.0);
+
+        assert_eq!(cost.fills.len(), 2);
+        assert_eq!(cost.fills[0].exchange, "Binance");
+        assert!(close(cost.fills[0].base, 2.0));
+        assert!(close(cost.fills[0].quote, 204.0));
+        assert_eq!(cost.fills[1].exchange, "Bitstamp");
+        assert!(close(cost.fills[1].base, 2.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sell_quote() -> Result<()> {
+        let cost = quote(&book(), Side::Sell, Quantity::Quote(148.0), true)?;
+
+        // 99 from the first bid, the remaining 49 buys half a unit at 98
+        assert!(close(cost.base, 1.5));
+        assert!(close(cost.quote, 148.0));
+        assert_eq!(cost.worst_price, 98.0);
+        assert!(close(cost.slippage, (100.0 - 148.0 / 1.5) / 100.0 * 1e4));
+        assert!(close(cost.effective_vwap, cost.vwap * 1.001));
+        assert!(!cost.insufficient);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_worst_price_fee_adjusted() -> Result<()> {
+        let ask = |exchange: &str, price, effective_price| Level {
+            exchange: String::from(exchange),
+            price,
+            amount: 1.0,
+            effective_price,
+        };
+        // the cheaper Bitstamp ask costs more once its fee is paid
+        let book = Summary {
+            asks: vec![ask("Binance", 102.0, 102.1), ask("Bitstamp", 101.5, 102.5)],
+            ..Default::default()
+        };
+
+        let cost = quote(&book, Side::Buy, Quantity::Base(2.0), true)?;
+        assert_eq!(cost.worst_price, 102.0);
+        assert!(close(cost.effective_vwap, 102.3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_insufficient() -> Result<()> {
+        let cost = quote(&book(), Side::Sell, Quantity::Base(5.0), false)?;
+
+        assert!(close(cost.base, 3.0));
+        assert!(cost.insufficient);
+
+        let empty = quote(&Summary::default(), Side::Buy, Quantity::Base(1.0), false)?;
+        assert!(empty.insufficient);
+        assert_eq!(empty.vwap, 0.0);
+        assert_eq!(empty.slippage, 0.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_quantity() {
+        assert!(quote(&book(), Side::Buy, Quantity::Base(0.0), false).is_err());
+        assert!(quote(&book(), Side::Buy, Quantity::Quote(f64::NAN), false).is_err());
+    }
+}
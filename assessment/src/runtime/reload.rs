@@ -0,0 +1,118 @@
+use super::providers::{check, Providers};
+use anyhow::Result;
+use common::{config::Config, ConfigRef};
+use std::{
+    fs,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    task, time,
+};
+use tracing::{info, warn};
+
+/// Reloads the configuration on SIGHUP, and on file changes when `watch_config` is set
+pub async fn watch(providers: Arc<Providers>) -> Result<()> {
+    let mut hangup = signal(SignalKind::hangup())?;
+    let mut interval = time::interval(Duration::from_secs(2));
+    let mut last = modified(&providers.config());
+    // the layers as last read, telling the admin changes since apart from the file's
+    let mut loaded = providers.config();
+
+    loop {
+        tokio::select! {
+            _ = hangup.recv() => info!("reload on hangup"),
+            _ = interval.tick() => {
+                let config = providers.config();
+
+                if !config.watch_config() || modified(&config) == last {
+                    continue;
+                }
+
+                info!("reload on change");
+            }
+        }
+
+        match reload(&providers, &loaded).await {
+            Ok(config) => loaded = config,
+            Err(e) => warn!("reload failed, keeping the running configuration - {}", e),
+        }
+
+        // a broken file is not retried until it changes again
+        last = modified(&providers.config());
+    }
+}
+
+/// Reads every configuration layer again and applies what changed since `loaded`
+async fn reload(providers: &Arc<Providers>, loaded: &ConfigRef) -> Result<ConfigRef> {
+    let config = Arc::new(Config::load_from(std::env::args_os())?);
+
+    let (providers, fresh, loaded) = (
+        Arc::clone(providers),
+        Arc::clone(&config),
+        Arc::clone(loaded),
+    );
+    let states = task::spawn_blocking(move || {
+        providers.reconfigure(|running| {
+            let config = rebase(running, &loaded, &fresh);
+            check(&Arc::new(config.clone()))?;
+            Ok(config)
+        })
+    })
+    .await??;
+
+    for state in states {
+        info!(
+            "reload {} - connected {} subscribed {}",
+            state.name, state.connected, state.subscribed
+        );
+    }
+
+    Ok(config)
+}
+
+/// The fresh layers, keeping the pair and top set through the admin service unless the layers
+/// changed them too
+fn rebase(running: &Config, loaded: &Config, fresh: &Config) -> Config {
+    let mut config = fresh.clone();
+
+    if fresh.pair() == loaded.pair() {
+        config = config.with_pair(running.pair());
+    }
+    if fresh.top() == loaded.top() {
+        config = config.with_top(running.top());
+    }
+
+    config
+}
+
+fn modified(config: &Config) -> Option<SystemTime> {
+    let path = config.config()?;
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebase() -> Result<()> {
+        let loaded = Config::load_toml("pair = \"ethbtc\"\ntop = 10\n", ["algo"])?;
+        let running = loaded.with_pair("btcusdt").with_top(3);
+
+        let fresh = Config::load_toml(
+            "pair = \"ethbtc\"\ntop = 10\nbreaker_threshold = 50\n",
+            ["algo"],
+        )?;
+        let config = rebase(&running, &loaded, &fresh);
+        assert_eq!((config.pair(), config.top()), ("btcusdt", 3));
+        assert_eq!(config.breaker_threshold(), 50.0);
+
+        let fresh = Config::load_toml("pair = \"xrpbtc\"\ntop = 10\n", ["algo"])?;
+        let config = rebase(&running, &loaded, &fresh);
+        assert_eq!((config.pair(), config.top()), ("xrpbtc", 3));
+
+        Ok(())
+    }
+}
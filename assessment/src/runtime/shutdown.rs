@@ -0,0 +1,42 @@
+use anyhow::Result;
+use std::time::Duration;
+use tokio::{
+    signal::{
+        self,
+        unix::{self, SignalKind},
+    },
+    task::JoinHandle,
+    time,
+};
+use tracing::{info, warn};
+
+/// Resolves on the first SIGTERM or SIGINT
+pub async fn signal() -> Result<()> {
+    let mut terminate = unix::signal(SignalKind::terminate())?;
+
+    tokio::select! {
+        _ = terminate.recv() => info!("shutdown on terminate"),
+        interrupt = signal::ctrl_c() => {
+            interrupt?;
+            info!("shutdown on interrupt");
+        }
+    }
+
+    Ok(())
+}
+
+/// Waits for the server to finish its in-flight calls, aborting it after `timeout`
+pub async fn drain<E>(mut server: JoinHandle<Result<(), E>>, timeout: Duration) -> Result<()>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    match time::timeout(timeout, &mut server).await {
+        Ok(served) => served??,
+        Err(_) => {
+            warn!("drain timed out after {:?}, aborting", timeout);
+            server.abort();
+        }
+    }
+
+    Ok(())
+}
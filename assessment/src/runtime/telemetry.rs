@@ -0,0 +1,89 @@
+use anyhow::Result;
+use common::config::{Config, LogFormat};
+use opentelemetry::{
+    global,
+    propagation::Extractor,
+    runtime,
+    sdk::{propagation::TraceContextPropagator, trace, Resource},
+    KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use tonic::codegen::http::{HeaderMap, Request};
+use tracing::{info_span, level_filters::LevelFilter, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+/// Logs filtered by `RUST_LOG`, plus spans to the OTLP collector when an endpoint is configured
+pub fn init(config: &Config) -> Result<()> {
+    // json lines carry the fields of the innermost span, exchange, pair and connection among them
+    let logs = match config.log_format() {
+        LogFormat::Text => fmt::layer().boxed(),
+        LogFormat::Json => fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .boxed(),
+    }
+    .with_filter(EnvFilter::from_default_env());
+
+    let spans = match config.otlp_endpoint() {
+        Some(endpoint) => {
+            global::set_text_map_propagator(TraceContextPropagator::new());
+
+            let tracer = opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(
+                    opentelemetry_otlp::new_exporter()
+                        .tonic()
+                        .with_endpoint(endpoint.as_str()),
+                )
+                .with_trace_config(
+                    trace::config()
+                        .with_resource(Resource::new(vec![KeyValue::new("service.name", "algo")])),
+                )
+                .install_batch(runtime::Tokio)?;
+
+            Some(
+                tracing_opentelemetry::layer()
+                    .with_tracer(tracer)
+                    .with_filter(LevelFilter::INFO),
+            )
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(logs)
+        .with(spans)
+        .try_init()?;
+
+    Ok(())
+}
+
+/// Flushes the spans still batched
+pub fn shutdown() {
+    global::shutdown_tracer_provider();
+}
+
+/// Span of an incoming gRPC call, continuing the caller's trace when it sent one
+pub fn request_span(request: &Request<()>) -> Span {
+    let span = info_span!("grpc", path = request.uri().path());
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&Headers(request.headers()))
+    });
+    span.set_parent(parent);
+    span
+}
+
+struct Headers<'a>(&'a HeaderMap);
+
+impl Extractor for Headers<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
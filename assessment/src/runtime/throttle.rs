@@ -0,0 +1,85 @@
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Keys kept before the expired ones are dropped
+const CAPACITY: usize = 256;
+
+struct Seen {
+    since: Instant,
+    suppressed: u64,
+}
+
+/// Lets an identical message through once per window, counting the repeats held back
+#[derive(Clone)]
+pub struct Throttle {
+    window: Duration,
+    seen: Arc<Mutex<HashMap<String, Seen>>>,
+}
+
+impl Throttle {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            seen: Arc::default(),
+        }
+    }
+
+    /// Repeats suppressed since the last time `key` went through, none while it is held back
+    pub fn allow(&self, key: &str) -> Option<u64> {
+        let mut seen = self.seen.lock();
+        let now = Instant::now();
+
+        if let Some(entry) = seen.get_mut(key) {
+            if now.duration_since(entry.since) < self.window {
+                entry.suppressed += 1;
+                return None;
+            }
+
+            let suppressed = entry.suppressed;
+            *entry = Seen {
+                since: now,
+                suppressed: 0,
+            };
+            return Some(suppressed);
+        }
+
+        if seen.len() >= CAPACITY {
+            seen.retain(|_, entry| now.duration_since(entry.since) < self.window);
+        }
+
+        seen.insert(
+            String::from(key),
+            Seen {
+                since: now,
+                suppressed: 0,
+            },
+        );
+
+        Some(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_allow() {
+        let throttle = Throttle::new(Duration::from_millis(50));
+
+        assert_eq!(throttle.allow("parse"), Some(0));
+        assert_eq!(throttle.allow("parse"), None);
+        assert_eq!(throttle.allow("parse"), None);
+        assert_eq!(throttle.allow("closed"), Some(0));
+
+        thread::sleep(Duration::from_millis(60));
+
+        assert_eq!(throttle.allow("parse"), Some(2));
+        assert_eq!(throttle.allow("parse"), None);
+    }
+}
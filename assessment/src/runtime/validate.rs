@@ -0,0 +1,262 @@
+use super::{metrics::MetricsRef, throttle::Throttle};
+use common::{
+    config::Policy,
+    orderbook::{Level, Summary},
+};
+use parking_lot::Mutex;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+/// Nonsense a provider can hand over, the `kind` label of the violation metric
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// Unparsable, infinite, zero or negative price
+    Price,
+    /// Unparsable, infinite, zero or negative amount
+    Amount,
+    /// Best bid at or above the best ask of the same exchange
+    Crossed,
+}
+
+impl Violation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Violation::Price => "price",
+            Violation::Amount => "amount",
+            Violation::Crossed => "crossed",
+        }
+    }
+
+    fn of(level: &Level) -> Option<Self> {
+        if !level.price.is_finite() || level.price <= 0.0 {
+            Some(Violation::Price)
+        } else if !level.amount.is_finite() || level.amount <= 0.0 {
+            Some(Violation::Amount)
+        } else {
+            None
+        }
+    }
+}
+
+/// Checks every book between the providers and the merge
+pub struct Validator {
+    metrics: MetricsRef,
+    throttle: Throttle,
+    /// Exchanges left out of the merge, until when
+    quarantined: Mutex<BTreeMap<String, Instant>>,
+}
+
+impl Validator {
+    pub fn new(metrics: MetricsRef) -> Self {
+        Self {
+            metrics,
+            throttle: Throttle::new(Duration::from_secs(10)),
+            quarantined: Mutex::default(),
+        }
+    }
+
+    /// Book to merge, none when the policy drops it or the exchange is quarantined
+    pub fn validate(
+        &self,
+        exchange: &str,
+        policy: Policy,
+        quarantine: Duration,
+        mut summary: Summary,
+    ) -> Option<Summary> {
+        if self.is_quarantined(exchange) {
+            return None;
+        }
+
+        let mut violations = Vec::new();
+
+        for levels in [&mut summary.bids, &mut summary.asks] {
+            levels.retain(|level| match Violation::of(level) {
+                Some(violation) => {
+                    violations.push(violation);
+                    false
+                }
+                None => true,
+            });
+        }
+
+        let bid = summary
+            .bids
+            .iter()
+            .map(|level| level.price)
+            .reduce(f64::max);
+        let ask = summary
+            .asks
+            .iter()
+            .map(|level| level.price)
+            .reduce(f64::min);
+        let crossed = matches!((bid, ask), (Some(bid), Some(ask)) if bid >= ask);
+        if crossed {
+            violations.push(Violation::Crossed);
+        }
+
+        if violations.is_empty() {
+            return Some(summary);
+        }
+
+        for violation in violations.iter() {
+            self.metrics.violation(exchange, violation.as_str());
+        }
+
+        let key = format!("{} {:?}", exchange, violations[0]);
+        if let Some(suppressed) = self.throttle.allow(&key) {
+            warn!(
+                exchange,
+                event = "validation",
+                suppressed,
+                "{} violations, first {}",
+                violations.len(),
+                violations[0].as_str()
+            );
+        }
+
+        match policy {
+            Policy::DropLevel if !crossed => Some(summary),
+            Policy::DropLevel | Policy::DropFrame => None,
+            Policy::Quarantine => {
+                warn!(
+                    exchange,
+                    event = "quarantine",
+                    "quarantined for {:?}",
+                    quarantine
+                );
+                self.quarantined
+                    .lock()
+                    .insert(String::from(exchange), Instant::now() + quarantine);
+                self.metrics.quarantined(exchange, true);
+                None
+            }
+        }
+    }
+
+    /// Whether the exchange is still out of the merge, lifting an expired quarantine
+    pub fn is_quarantined(&self, exchange: &str) -> bool {
+        let mut quarantined = self.quarantined.lock();
+
+        match quarantined.get(exchange) {
+            Some(until) if Instant::now() < *until => true,
+            Some(_) => {
+                quarantined.remove(exchange);
+                self.metrics.quarantined(exchange, false);
+                info!(exchange, event = "quarantine", "quarantine lifted");
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::Arc, thread};
+
+    fn level(price: f64, amount: f64) -> Level {
+        Level {
+            exchange: String::from("Binance"),
+            price,
+            amount,
+            ..Default::default()
+        }
+    }
+
+    fn book(bids: Vec<Level>, asks: Vec<Level>) -> Summary {
+        Summary {
+            bids,
+            asks,
+            ..Default::default()
+        }
+    }
+
+    fn validator() -> Validator {
+        Validator::new(Arc::default())
+    }
+
+    const QUARANTINE: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn test_valid() {
+        let summary = book(vec![level(1.0, 1.0)], vec![level(2.0, 1.0)]);
+
+        for policy in [Policy::DropLevel, Policy::DropFrame, Policy::Quarantine] {
+            let validated = validator().validate("binance", policy, QUARANTINE, summary.clone());
+            assert_eq!(validated, Some(summary.clone()));
+        }
+    }
+
+    #[test]
+    fn test_drop_level() {
+        let validator = validator();
+        let summary = book(
+            vec![level(f64::NAN, 1.0), level(1.0, 1.0), level(0.9, 0.0)],
+            vec![level(2.0, -1.0), level(f64::INFINITY, 1.0), level(2.1, 1.0)],
+        );
+
+        let validated = validator
+            .validate("binance", Policy::DropLevel, QUARANTINE, summary)
+            .unwrap();
+        assert_eq!(validated.bids, [level(1.0, 1.0)]);
+        assert_eq!(validated.asks, [level(2.1, 1.0)]);
+
+        let text = validator.metrics.render().unwrap();
+        assert!(text
+            .contains("algo_validation_violations_total{exchange=\"binance\",kind=\"price\"} 2"));
+        assert!(text
+            .contains("algo_validation_violations_total{exchange=\"binance\",kind=\"amount\"} 2"));
+    }
+
+    #[test]
+    fn test_crossed() {
+        let summary = book(vec![level(2.0, 1.0)], vec![level(2.0, 1.0)]);
+
+        for policy in [Policy::DropLevel, Policy::DropFrame] {
+            let validator = validator();
+            assert!(validator
+                .validate("binance", policy, QUARANTINE, summary.clone())
+                .is_none());
+            assert!(!validator.is_quarantined("binance"));
+        }
+    }
+
+    #[test]
+    fn test_drop_frame() {
+        let summary = book(vec![level(1.0, 1.0)], vec![level(2.0, f64::NAN)]);
+
+        assert!(validator()
+            .validate("binance", Policy::DropFrame, QUARANTINE, summary)
+            .is_none());
+    }
+
+    #[test]
+    fn test_quarantine() {
+        let validator = validator();
+        let bad = book(vec![level(-1.0, 1.0)], vec![level(2.0, 1.0)]);
+        let good = book(vec![level(1.0, 1.0)], vec![level(2.0, 1.0)]);
+        let quarantine = Duration::from_millis(50);
+
+        assert!(validator
+            .validate("binance", Policy::Quarantine, quarantine, bad)
+            .is_none());
+        assert!(validator.is_quarantined("binance"));
+        assert!(!validator.is_quarantined("bitstamp"));
+        assert!(validator
+            .validate("binance", Policy::Quarantine, quarantine, good.clone())
+            .is_none());
+
+        thread::sleep(quarantine);
+
+        assert_eq!(
+            validator.validate("binance", Policy::Quarantine, quarantine, good.clone()),
+            Some(good)
+        );
+        let text = validator.metrics.render().unwrap();
+        assert!(text.contains("algo_quarantined{exchange=\"binance\"} 0"));
+    }
+}
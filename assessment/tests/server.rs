@@ -0,0 +1,332 @@
+use anyhow::{bail, Result};
+use assessment::runtime::orderbook::Orderbook;
+use common::{
+    config::Config,
+    orderbook::{
+        admin_client::AdminClient, admin_server::AdminServer,
+        orderbook_aggregator_client::OrderbookAggregatorClient,
+        orderbook_aggregator_server::OrderbookAggregatorServer, quote_request::Quantity, Empty,
+        Phase, ProviderRequest, QuoteRequest, Side, Summary,
+    },
+};
+use std::{sync::Arc, time::Duration};
+use testkit::{binance, bitstamp, MockServer, Step};
+use tokio::{net::TcpListener, time::timeout};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    transport::{Channel, Server},
+    Code, Streaming,
+};
+
+const WAIT: Duration = Duration::from_secs(10);
+
+/// Full gRPC server merging the mock exchanges, and a channel to it
+async fn serve(binance: &MockServer, bitstamp: &MockServer, args: &[&str]) -> Result<Channel> {
+    let (binance, bitstamp) = (binance.url(), bitstamp.url());
+    let mut argv = vec![
+        "algo",
+        "--binance-url",
+        binance.as_str(),
+        "--bitstamp-url",
+        bitstamp.as_str(),
+    ];
+    argv.extend_from_slice(args);
+    let config = Config::load_from(argv)?;
+
+    let orderbook = Orderbook::new(Arc::new(config))?;
+    orderbook.connect()?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?;
+
+    tokio::spawn(
+        Server::builder()
+            .add_service(AdminServer::new(orderbook.admin()))
+            .add_service(OrderbookAggregatorServer::new(orderbook))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    Ok(Channel::from_shared(format!("http://{}", address))?
+        .connect()
+        .await?)
+}
+
+fn depth(update_id: u64, bid: f64) -> Step {
+    Step::Send(binance::depth(
+        update_id,
+        &[(bid, 1.0)],
+        &[(bid + 0.0004, 2.0)],
+    ))
+}
+
+/// Bitstamp books at a steady pace, enough to outlast any test
+fn steady(count: u64) -> Vec<Step> {
+    (0..count)
+        .flat_map(|i| {
+            [
+                Step::Send(bitstamp::book(
+                    "ethbtc",
+                    1_682_624_742_000_000 + i,
+                    &[(0.0630, 0.5)],
+                    &[(0.0660, 0.4)],
+                )),
+                Step::Pause(Duration::from_millis(20)),
+            ]
+        })
+        .collect()
+}
+
+fn has_bid(summary: &Summary, exchange: &str, price: f64) -> bool {
+    summary
+        .bids
+        .iter()
+        .any(|level| level.exchange == exchange && level.price == price)
+}
+
+async fn until<F>(stream: &mut Streaming<Summary>, found: F) -> Result<Summary>
+where
+    F: Fn(&Summary) -> bool,
+{
+    timeout(WAIT, async {
+        while let Some(summary) = stream.message().await? {
+            if found(&summary) {
+                return Ok(summary);
+            }
+        }
+        bail!("stream ended")
+    })
+    .await?
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_book_summary_merged() -> Result<()> {
+    let binance = MockServer::binance(vec![vec![depth(1, 0.0647)]])?;
+    let bitstamp = MockServer::bitstamp(vec![vec![Step::Send(bitstamp::book(
+        "ethbtc",
+        1_682_624_742_462_361,
+        &[(0.0648, 0.5)],
+        &[(0.0652, 0.4)],
+    ))]])?;
+
+    let mut client = OrderbookAggregatorClient::new(serve(&binance, &bitstamp, &[]).await?);
+
+    let summary = timeout(WAIT, client.book_summary(Empty::default()))
+        .await??
+        .into_inner();
+
+    assert_eq!(summary.bids.len(), 2);
+    assert_eq!(summary.bids[0].exchange, "Bitstamp");
+    assert_eq!(summary.bids[0].price, 0.0648);
+    assert_eq!(summary.bids[1].exchange, "Binance");
+    assert_eq!(summary.asks[0].exchange, "Binance");
+    assert_eq!(summary.asks[0].price, 0.0651);
+    assert_eq!(summary.asks[1].amount, 0.4);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_quote_across_exchanges() -> Result<()> {
+    let binance = MockServer::binance(vec![vec![depth(1, 0.0647)]])?;
+    let bitstamp = MockServer::bitstamp(vec![vec![Step::Send(bitstamp::book(
+        "ethbtc",
+        1_682_624_742_462_361,
+        &[(0.0648, 0.5)],
+        &[(0.0652, 0.4)],
+    ))]])?;
+
+    let mut client = OrderbookAggregatorClient::new(serve(&binance, &bitstamp, &[]).await?);
+
+    // all 2 binance asks at 0.0651, then 0.2 of bitstamp at 0.0652
+    let request = QuoteRequest {
+        side: Side::Buy as i32,
+        quantity: Some(Quantity::Base(2.2)),
+    };
+    let cost = timeout(WAIT, client.quote(request)).await??.into_inner();
+
+    assert!(!cost.insufficient);
+    assert_eq!(cost.worst_price, 0.0652);
+    assert!((cost.vwap - (2.0 * 0.0651 + 0.2 * 0.0652) / 2.2).abs() < 1e-12);
+    assert_eq!(cost.fills.len(), 2);
+    assert_eq!(cost.fills[0].exchange, "Binance");
+    assert!((cost.fills[1].base - 0.2).abs() < 1e-12);
+
+    let request = QuoteRequest {
+        side: Side::Sell as i32,
+        quantity: Some(Quantity::Base(-1.0)),
+    };
+    let status = client.quote(request).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_price_stream() -> Result<()> {
+    let binance = MockServer::binance(vec![vec![depth(1, 0.0647)]])?;
+    let bitstamp = MockServer::bitstamp(vec![steady(500)])?;
+
+    let channel = serve(&binance, &bitstamp, &["--pricing", "mid,exchange"]).await?;
+    let mut client = OrderbookAggregatorClient::new(channel);
+    let mut stream = client.price_stream(Empty::default()).await?.into_inner();
+
+    // bitstamp 0.0630 / 0.0660 on its own at first, then binance 0.0647 / 0.0651 joins
+    let prices = timeout(WAIT, async {
+        while let Some(prices) = stream.message().await? {
+            if (prices.mid - 0.0649).abs() < 1e-12 {
+                return Ok(prices);
+            }
+        }
+        bail!("stream ended")
+    })
+    .await??;
+
+    assert!((prices.exchange_mid - (0.0649 + 0.0645) / 2.0).abs() < 1e-12);
+    assert_eq!(prices.micro_price, 0.0);
+    assert_eq!(prices.depth_mid, 0.0);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_arbitrage_window() -> Result<()> {
+    // binance asks 0.0625 while bitstamp bids 0.0630, then both sit apart
+    let mut script = vec![Step::Send(binance::depth(
+        1,
+        &[(0.0620, 1.0)],
+        &[(0.0625, 0.3)],
+    ))];
+    script.push(Step::Pause(Duration::from_millis(200)));
+    script.extend((2..100).map(|update_id| depth(update_id, 0.0640)));
+
+    let binance = MockServer::binance(vec![script])?;
+    let bitstamp = MockServer::bitstamp(vec![steady(500)])?;
+
+    let mut client = OrderbookAggregatorClient::new(serve(&binance, &bitstamp, &[]).await?);
+    let mut stream = client
+        .arbitrage_stream(Empty::default())
+        .await?
+        .into_inner();
+
+    let start = timeout(WAIT, stream.message()).await??.expect("start");
+    assert_eq!(start.phase, Phase::Start as i32);
+    assert_eq!(
+        (start.buy.as_str(), start.sell.as_str()),
+        ("Binance", "Bitstamp")
+    );
+    assert_eq!((start.ask, start.bid, start.amount), (0.0625, 0.0630, 0.3));
+
+    let end = timeout(WAIT, stream.message()).await??.expect("end");
+    assert_eq!(end.phase, Phase::End as i32);
+    assert_eq!(end.start_timestamp, start.start_timestamp);
+    assert!(end.duration > 0);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_stream_past_malformed_frames() -> Result<()> {
+    // failed frames leave binance out of the merge, so its first book has to outlast the subscribe
+    let binance = MockServer::binance(vec![vec![
+        depth(1, 0.0640),
+        Step::Pause(Duration::from_millis(500)),
+        Step::Send(String::from("not json")),
+        Step::Send(String::from(
+            r#"{"lastUpdateId":2,"bids":[["x","1"]],"asks":[]}"#,
+        )),
+        depth(3, 0.0642),
+    ]])?;
+    let bitstamp = MockServer::bitstamp(vec![steady(500)])?;
+
+    let mut client = OrderbookAggregatorClient::new(serve(&binance, &bitstamp, &[]).await?);
+    let mut stream = client
+        .book_summary_stream(Empty::default())
+        .await?
+        .into_inner();
+
+    until(&mut stream, |summary| has_bid(summary, "Binance", 0.0640)).await?;
+    until(&mut stream, |summary| has_bid(summary, "Binance", 0.0642)).await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_reconnect_after_disconnect() -> Result<()> {
+    let binance = MockServer::binance(vec![
+        vec![
+            depth(1, 0.0640),
+            Step::Pause(Duration::from_millis(500)),
+            Step::Disconnect,
+        ],
+        vec![depth(2, 0.0645)],
+    ])?;
+    let bitstamp = MockServer::bitstamp(vec![steady(500)])?;
+
+    let channel = serve(&binance, &bitstamp, &[]).await?;
+    let mut client = OrderbookAggregatorClient::new(channel.clone());
+    let mut admin = AdminClient::new(channel);
+
+    let mut stream = client
+        .book_summary_stream(Empty::default())
+        .await?
+        .into_inner();
+
+    until(&mut stream, |summary| has_bid(summary, "Binance", 0.0640)).await?;
+
+    // the broken connection drops out of the merge, bitstamp is still published
+    for _ in 0..3 {
+        let summary = until(&mut stream, |summary| {
+            summary.bids.iter().all(|level| level.exchange != "Binance")
+        })
+        .await?;
+        assert!(!summary.bids.is_empty());
+    }
+
+    let state = admin
+        .reconnect(ProviderRequest {
+            name: String::from("binance"),
+        })
+        .await?
+        .into_inner();
+    assert!(state.connected && state.subscribed);
+
+    until(&mut stream, |summary| has_bid(summary, "Binance", 0.0645)).await?;
+    assert_eq!(binance.connections(), 2);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_quarantine_crossed_exchange() -> Result<()> {
+    let mut script = vec![Step::Send(binance::depth(
+        1,
+        &[(0.0700, 1.0)],
+        &[(0.0600, 1.0)],
+    ))];
+    script.extend((2..100).map(|update_id| depth(update_id, 0.0640)));
+
+    let binance = MockServer::binance(vec![script])?;
+    let bitstamp = MockServer::bitstamp(vec![steady(500)])?;
+
+    let channel = serve(&binance, &bitstamp, &["--validation", "quarantine"]).await?;
+    let mut client = OrderbookAggregatorClient::new(channel);
+    let mut stream = client
+        .book_summary_stream(Empty::default())
+        .await?
+        .into_inner();
+
+    // binance stays out of the merge for the whole quarantine, valid books included
+    for _ in 0..20 {
+        let summary = until(&mut stream, |_| true).await?;
+        assert!(summary
+            .bids
+            .iter()
+            .all(|level| level.exchange == "Bitstamp"));
+        assert!(summary
+            .asks
+            .iter()
+            .all(|level| level.exchange == "Bitstamp"));
+    }
+
+    Ok(())
+}
@@ -1,13 +1,19 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
 use common::{
+    chaos::ChaosTransport,
+    clock, frame,
     orderbook::{Level, Summary},
-    ConfigRef, Provider,
+    recorder::Recorder,
+    registry::Registry,
+    transport::Tungstenite,
+    ConfigRef, Provider, Transport,
 };
-use log::info;
-use parking_lot::RwLock;
+use log::{info, warn};
 use serde::Deserialize;
-use std::net::TcpStream;
-use tungstenite::{connect, stream::MaybeTlsStream, Message, WebSocket};
+use tungstenite::Message;
+use url::Url;
+
+const NAME: &str = "Binance";
 
 #[derive(Deserialize)]
 struct Depth {
@@ -17,21 +23,22 @@ struct Depth {
     asks: Vec<[String; 2]>,
 }
 
-pub struct Binance {
+pub struct Binance<T: Transport = Tungstenite> {
     _config: ConfigRef,
-    socket: RwLock<WebSocket<MaybeTlsStream<TcpStream>>>,
+    transport: T,
+    recorder: Recorder,
 }
 
-impl Drop for Binance {
+impl<T: Transport> Drop for Binance<T> {
     fn drop(&mut self) {
         info!("binance disconnect");
-        self.socket.write().close(None).ok();
+        self.transport.close();
     }
 }
 
-impl Provider for Binance {
+impl<T: Transport> Provider for Binance<T> {
     fn name(&self) -> &'static str {
-        "Binance"
+        NAME
     }
 
     fn subscribe(&self) -> Result<()> {
@@ -43,54 +50,208 @@ impl Provider for Binance {
     }
 
     fn summary(&self) -> Result<Summary> {
-        let message = self.read()?;
-        let depth: Depth = serde_json::from_str(message.to_text()?)?;
-
-        let mut summary = Summary::default();
-
-        for order in depth.asks {
-            let level = Level {
-                exchange: String::from(self.name()),
-                price: order[0].parse()?,
-                amount: order[1].parse()?,
-            };
-            summary.asks.push(level)
-        }
+        let message = frame::read_text(|| self.read())?;
+        parse(message.as_str(), clock::micros())
+    }
+}
 
-        for order in depth.bids {
-            let level = Level {
-                exchange: String::from(self.name()),
-                price: order[0].parse()?,
-                amount: order[1].parse()?,
-            };
-            summary.bids.push(level)
-        }
+/// Depth message received at `received` unix microseconds, as a summary
+///
+/// An unparsable level comes out as NaN and is left to validation, only a broken message fails.
+pub fn parse(message: &str, received: u64) -> Result<Summary> {
+    let depth: Depth = serde_json::from_str(message)?;
+
+    // partial depth streams carry no event time, the event timestamp stays unknown
+    let mut summary = Summary {
+        receive_timestamp: received,
+        ..Default::default()
+    };
 
-        Ok(summary)
+    for order in depth.asks {
+        let level = Level {
+            exchange: String::from(NAME),
+            price: order[0].parse().unwrap_or(f64::NAN),
+            amount: order[1].parse().unwrap_or(f64::NAN),
+            ..Default::default()
+        };
+        summary.asks.push(level)
     }
-}
 
-impl Binance {
-    pub fn new(config: ConfigRef) -> Self {
-        let url = config.binance_url();
+    for order in depth.bids {
+        let level = Level {
+            exchange: String::from(NAME),
+            price: order[0].parse().unwrap_or(f64::NAN),
+            amount: order[1].parse().unwrap_or(f64::NAN),
+            ..Default::default()
+        };
+        summary.bids.push(level)
+    }
 
-        let depth = format!("{}@depth20@100ms", config.pair());
-        let url = url.join(depth.as_str()).expect("failed to build url");
+    Ok(summary)
+}
 
+impl<T: Transport> Binance<T> {
+    pub fn new(config: ConfigRef, connection: u64) -> Result<Self> {
+        let url = url(&config);
         info!("binance connect {}", url);
+        warn!("binance partial depth stream has no event time, its latency is not measured");
+
+        let transport = T::connect(&config, &url)?;
 
-        let (socket, _) = connect(url).expect("failed to connect to binance");
+        Ok(Self::with_transport(config, connection, transport))
+    }
 
+    /// Provider reading from an already connected transport
+    pub fn with_transport(config: ConfigRef, connection: u64, transport: T) -> Self {
         Self {
+            recorder: Recorder::open(&config, "binance", connection),
             _config: config,
-            socket: RwLock::new(socket),
+            transport,
         }
     }
 
     fn read(&self) -> Result<Message> {
-        self.socket
-            .write()
-            .read_message()
-            .with_context(|| "Failed to read")
+        let message = self.transport.receive()?;
+        self.recorder.record(&message);
+        Ok(message)
+    }
+}
+
+/// Depth stream of the pair, the subscription is part of the URL
+fn url(config: &ConfigRef) -> Url {
+    let levels = config.depth("binance").unwrap_or(20);
+    let depth = match config.update_speed("binance") {
+        Some(1000) => format!("{}@depth{}", config.pair(), levels),
+        _ => format!("{}@depth{}@100ms", config.pair(), levels),
+    };
+    config
+        .binance_url()
+        .join(depth.as_str())
+        .expect("failed to build url")
+}
+
+/// Registers the provider under `binance`
+pub fn register(registry: &mut Registry) {
+    registry.register("binance", |config, connection| {
+        match config.chaos("binance") {
+            true => Ok(Box::new(Binance::<ChaosTransport<Tungstenite>>::new(
+                config, connection,
+            )?)),
+            false => Ok(Box::new(Binance::<Tungstenite>::new(config, connection)?)),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{config::Config, transport::Memory};
+    use std::sync::Arc;
+    use testkit::{binance::depth, MockServer, Step};
+
+    const DEPTH: &str = r#"{"lastUpdateId":160,"bids":[["0.06466000","0.50000000"],["0.06465000","1.50000000"]],"asks":[["0.06468000","0.40000000"]]}"#;
+
+    fn memory(frames: &[&str]) -> (Binance<Arc<Memory>>, Arc<Memory>) {
+        let memory = Arc::new(Memory::default());
+        for frame in frames {
+            memory.push_text(frame);
+        }
+        let provider = Binance::with_transport(Config::as_ref(), 0, Arc::clone(&memory));
+        (provider, memory)
+    }
+
+    #[test]
+    fn test_connect_url() -> Result<()> {
+        let provider = Binance::<Memory>::new(Config::as_ref(), 0)?;
+        assert_eq!(
+            provider.transport.url().map(Url::as_str),
+            Some("wss://stream.binance.com:9443/ws/ethbtc@depth20@100ms")
+        );
+
+        let config = Config::load_from(["algo", "--pair", "btcusdt"])?;
+        let provider = Binance::<Memory>::new(Arc::new(config), 0)?;
+        assert_eq!(
+            provider.transport.url().map(Url::as_str),
+            Some("wss://stream.binance.com:9443/ws/btcusdt@depth20@100ms")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_drop_closes() {
+        let (provider, memory) = memory(&[]);
+        assert_eq!(provider.name(), "Binance");
+        assert!(provider.subscribe().is_ok());
+        assert!(memory.sent().is_empty());
+
+        drop(provider);
+        assert!(memory.is_closed());
+    }
+
+    #[test]
+    fn test_summary_well() -> Result<()> {
+        let (provider, _memory) = memory(&[DEPTH]);
+
+        let summary = provider.summary()?;
+        assert_eq!(summary.event_timestamp, 0);
+        assert!(summary.receive_timestamp > 0);
+        assert_eq!(summary.bids.len(), 2);
+        assert_eq!(summary.bids[1].price, 0.06465);
+        assert_eq!(summary.bids[1].amount, 1.5);
+        assert_eq!(summary.asks[0].exchange, "Binance");
+        assert_eq!(summary.asks[0].amount, 0.4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_skips_control() -> Result<()> {
+        let (provider, memory) = memory(&[]);
+        memory.push(Message::Ping(Vec::new()));
+        memory.push_text(DEPTH);
+
+        assert_eq!(provider.summary()?.asks.len(), 1);
+        assert_eq!(memory.pending(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_fail() -> Result<()> {
+        let (provider, memory) = memory(&[
+            r#"{"lastUpdateId":1,"bids":[["x","1"],["0.06","1"]],"asks":[]}"#,
+            "{}",
+        ]);
+        memory.push_error("Failed to read");
+
+        // one bad level does not cost the frame
+        let summary = provider.summary()?;
+        assert!(summary.bids[0].price.is_nan());
+        assert_eq!(summary.bids[1].price, 0.06);
+
+        assert!(provider.summary().is_err());
+        assert_eq!(
+            provider.summary().unwrap_err().to_string(),
+            "Failed to read"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_network() -> Result<()> {
+        let server = MockServer::binance(vec![vec![
+            Step::Send(depth(1, &[(0.06466, 0.5)], &[(0.06468, 0.4)])),
+            Step::Disconnect,
+        ]])?;
+
+        let config = Config::load_from(["algo", "--binance-url", server.url().as_str()])?;
+        let provider = Binance::<Tungstenite>::new(Arc::new(config), 0)?;
+
+        assert_eq!(provider.summary()?.bids[0].price, 0.06466);
+        assert!(provider.summary().is_err());
+
+        Ok(())
     }
 }
@@ -1,7 +1,20 @@
 pub(crate) mod orderbook;
 pub(crate) mod response;
-pub(crate) mod socket;
 
 pub mod provider;
 
-pub use provider::Bitstamp;
+pub use provider::{parse, Bitstamp};
+
+use common::{chaos::ChaosTransport, registry::Registry, transport::Tungstenite};
+
+/// Registers the provider under `bitstamp`
+pub fn register(registry: &mut Registry) {
+    registry.register("bitstamp", |config, connection| {
+        match config.chaos("bitstamp") {
+            true => Ok(Box::new(Bitstamp::<ChaosTransport<Tungstenite>>::new(
+                config, connection,
+            )?)),
+            false => Ok(Box::new(Bitstamp::<Tungstenite>::new(config, connection)?)),
+        }
+    });
+}
@@ -1,3 +1,4 @@
+use anyhow::Result;
 use serde::Deserialize;
 use std::slice::Iter;
 
@@ -5,17 +6,20 @@ use std::slice::Iter;
 pub struct OrderBook {
     #[serde(rename = "timestamp")]
     _timestamp: String,
-    #[serde(rename = "microtimestamp")]
-    _microtimestamp: String,
+    microtimestamp: String,
     bids: Vec<[String; 2]>,
     asks: Vec<[String; 2]>,
 }
 
 impl OrderBook {
-    pub fn asks(&self) -> Iter<[String; 2]> {
+    /// Exchange event time in unix microseconds
+    pub fn microtimestamp(&self) -> Result<u64> {
+        Ok(self.microtimestamp.parse()?)
+    }
+    pub fn asks(&self) -> Iter<'_, [String; 2]> {
         self.asks.iter()
     }
-    pub fn bids(&self) -> Iter<[String; 2]> {
+    pub fn bids(&self) -> Iter<'_, [String; 2]> {
         self.bids.iter()
     }
 }
@@ -1,31 +1,33 @@
 use crate::response::Response;
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use common::{
+    clock, frame,
     orderbook::{Level, Summary},
-    ConfigRef, Provider,
+    recorder::Recorder,
+    transport::Tungstenite,
+    ConfigRef, Provider, Transport,
 };
-use log::info;
-use mockall_double::double;
+use log::{debug, info};
 use tungstenite::Message;
 
-#[double]
-use crate::socket::Sock;
+const NAME: &str = "Bitstamp";
 
-pub struct Bitstamp {
+pub struct Bitstamp<T: Transport = Tungstenite> {
     config: ConfigRef,
-    socket: Sock,
+    transport: T,
+    recorder: Recorder,
 }
 
-impl Drop for Bitstamp {
+impl<T: Transport> Drop for Bitstamp<T> {
     fn drop(&mut self) {
         info!("bitstamp disconnect");
-        self.socket.close();
+        self.transport.close();
     }
 }
 
-impl Provider for Bitstamp {
+impl<T: Transport> Provider for Bitstamp<T> {
     fn name(&self) -> &'static str {
-        "Bitstamp"
+        NAME
     }
 
     fn subscribe(&self) -> Result<()> {
@@ -33,13 +35,13 @@ impl Provider for Bitstamp {
             "{{\"event\":\"bts:subscribe\",\"data\":{{\"channel\":\"order_book_{}\"}}}}",
             self.config.pair()
         );
-        info!("bitstamp subscribe - {}", subscribe.as_str());
+        debug!("bitstamp subscribe - {}", subscribe.as_str());
 
         let request = Message::Text(subscribe);
         self.write(request)?;
 
         let response = self.read()?;
-        info!("bitstamp subscribe - {}", response.to_text()?);
+        debug!("bitstamp subscribe - {}", response.to_text()?);
 
         Ok(())
     }
@@ -49,236 +51,201 @@ impl Provider for Bitstamp {
             "{{\"event\":\"bts:unsubscribe\",\"data\":{{\"channel\":\"order_book_{}\"}}}}",
             self.config.pair()
         );
-        info!("bitstamp unsubscribe - {}", unsubscribe.as_str());
+        debug!("bitstamp unsubscribe - {}", unsubscribe.as_str());
 
         let request = Message::Text(unsubscribe);
         self.write(request)?;
 
         let response = self.read()?;
-        info!("bitstamp unsubscribe - {}", response.to_text()?);
+        debug!("bitstamp unsubscribe - {}", response.to_text()?);
 
         Ok(())
     }
 
     fn summary(&self) -> Result<Summary> {
-        match self.read()? {
-            Message::Text(message) => {
-                let response: Response = serde_json::from_str(message.as_str())?;
-                let orderbook = response.orderbook();
-
-                let mut summary = Summary::default();
-
-                for order in orderbook.asks() {
-                    let level = Level {
-                        exchange: String::from(self.name()),
-                        price: order[0].parse()?,
-                        amount: order[1].parse()?,
-                    };
-                    summary.asks.push(level);
-                }
-
-                for order in orderbook.bids() {
-                    let level = Level {
-                        exchange: String::from(self.name()),
-                        price: order[0].parse()?,
-                        amount: order[1].parse()?,
-                    };
-                    summary.bids.push(level);
-                }
-
-                Ok(summary)
+        loop {
+            let message = frame::read_text(|| self.read())?;
+
+            if let Some(summary) = parse(message.as_str(), clock::micros())? {
+                return Ok(summary);
             }
-            _ => Err(anyhow!("unexpected")),
         }
     }
 }
 
-impl Bitstamp {
-    pub fn new(config: ConfigRef) -> Self {
+/// Order book message received at `received` unix microseconds as a summary, none for other events
+///
+/// An unparsable level comes out as NaN and is left to validation, only a broken message fails.
+pub fn parse(message: &str, received: u64) -> Result<Option<Summary>> {
+    let response: Response = serde_json::from_str(message)?;
+
+    let orderbook = match response.orderbook() {
+        Some(orderbook) => orderbook,
+        None => return Ok(None),
+    };
+
+    let mut summary = Summary {
+        event_timestamp: orderbook.microtimestamp()?,
+        receive_timestamp: received,
+        ..Default::default()
+    };
+
+    for order in orderbook.asks() {
+        let level = Level {
+            exchange: String::from(NAME),
+            price: order[0].parse().unwrap_or(f64::NAN),
+            amount: order[1].parse().unwrap_or(f64::NAN),
+            ..Default::default()
+        };
+        summary.asks.push(level);
+    }
+
+    for order in orderbook.bids() {
+        let level = Level {
+            exchange: String::from(NAME),
+            price: order[0].parse().unwrap_or(f64::NAN),
+            amount: order[1].parse().unwrap_or(f64::NAN),
+            ..Default::default()
+        };
+        summary.bids.push(level);
+    }
+
+    Ok(Some(summary))
+}
+
+impl<T: Transport> Bitstamp<T> {
+    pub fn new(config: ConfigRef, connection: u64) -> Result<Self> {
         let url = config.bitstamp_url();
         info!("bitstamp connect - {}", url);
 
-        let socket = Sock::new(url).expect("failed to connect to bitstamp");
+        let transport = T::connect(&config, url)?;
 
-        Self { config, socket }
+        Ok(Self::with_transport(config, connection, transport))
+    }
+
+    /// Provider talking over an already connected transport
+    pub fn with_transport(config: ConfigRef, connection: u64, transport: T) -> Self {
+        let recorder = Recorder::open(&config, "bitstamp", connection);
+
+        Self {
+            config,
+            transport,
+            recorder,
+        }
     }
 
     fn write(&self, request: Message) -> Result<()> {
-        self.socket.write_message(request)
+        self.transport.send(request)
     }
 
     fn read(&self) -> Result<Message> {
-        self.socket.read_message()
+        let message = self.transport.receive()?;
+        self.recorder.record(&message);
+        Ok(message)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::socket::MockSock;
-    use anyhow::bail;
-    use common::config::Config;
-    use mockall::predicate::eq;
+    use common::{config::Config, transport::Memory};
+    use std::sync::Arc;
     use url::Url;
 
+    fn memory() -> (Bitstamp<Arc<Memory>>, Arc<Memory>) {
+        let memory = Arc::new(Memory::default());
+        let provider = Bitstamp::with_transport(Config::as_ref(), 0, Arc::clone(&memory));
+        (provider, memory)
+    }
+
     #[test]
     fn test_connect_well() {
-        let context = MockSock::new_context();
-
-        context
-            .expect()
-            .with(eq(Url::parse("wss://ws.bitstamp.net").unwrap()))
-            .returning(|_| {
-                let mut mocked = MockSock::default();
-                mocked.expect_close().once();
-                Ok(mocked)
-            });
-
-        let _provider = Bitstamp::new(Config::as_ref());
+        let provider = Bitstamp::<Arc<Memory>>::new(Config::as_ref(), 0).unwrap();
+        let memory = Arc::clone(&provider.transport);
+
+        assert_eq!(
+            memory.url(),
+            Some(&Url::parse("wss://ws.bitstamp.net").unwrap())
+        );
+
+        drop(provider);
+        assert!(memory.is_closed());
     }
 
     #[test]
-    #[should_panic]
     fn test_connect_fail() {
-        let context = MockSock::new_context();
+        let config = Config::load_from(["algo", "--bitstamp-url", "ws://127.0.0.1:1"]).unwrap();
 
-        context
-            .expect()
-            .returning(|_url| bail!("Failed to connect"));
-
-        let _provider = Bitstamp::new(Config::as_ref());
+        assert!(Bitstamp::<Tungstenite>::new(Arc::new(config), 0).is_err());
     }
 
     #[test]
     fn test_name() {
-        let context = MockSock::new_context();
-        context.expect().returning(|_url| {
-            let mut mocked = MockSock::default();
-            mocked.expect_close().once();
-            Ok(mocked)
-        });
-
-        let provider = Bitstamp::new(Config::as_ref());
+        let (provider, _memory) = memory();
         assert_eq!(provider.name(), "Bitstamp");
     }
 
     #[test]
     fn test_subscribe_well() {
-        let context = MockSock::new_context();
-
-        context.expect().returning(|_url| {
-            let mut mocked = MockSock::default();
-
-            mocked
-                .expect_write_message()
-                .withf(|message| {
-                    message.to_string().eq("{\"event\":\"bts:subscribe\",\"data\":{\"channel\":\"order_book_ethbtc\"}}")
-                })
-                .returning(|_| Ok(()));
-
-            mocked
-                .expect_read_message()
-                .returning(|| Ok(Message::Text(String::from("{{}}"))));
-
-            mocked.expect_close().once();
-            Ok(mocked)
-        });
-
-        let provider = Bitstamp::new(Config::as_ref());
+        let (provider, memory) = memory();
+        memory.push_text("{{}}");
 
         assert!(provider.subscribe().is_ok());
+        assert_eq!(
+            memory.sent(),
+            [Message::Text(String::from(
+                "{\"event\":\"bts:subscribe\",\"data\":{\"channel\":\"order_book_ethbtc\"}}"
+            ))]
+        );
     }
 
     #[test]
     fn test_subscribe_write_fail() {
-        let context = MockSock::new_context();
-        context.expect().returning(|_url| {
-            let mut mocked = MockSock::default();
-
-            mocked
-                .expect_write_message()
-                .returning(|_| bail!("Failed to write"));
-
-            mocked.expect_read_message().never();
-
-            mocked.expect_close().once();
-            Ok(mocked)
-        });
-
-        let provider = Bitstamp::new(Config::as_ref());
+        let (provider, memory) = memory();
+        memory.push_text("{{}}");
+        memory.fail_send("Failed to write");
 
         assert!(provider.subscribe().is_err());
+        assert_eq!(memory.pending(), 1);
     }
 
     #[test]
     fn test_subscribe_read_fail() {
-        let context = MockSock::new_context();
-        context.expect().returning(|_url| {
-            let mut mocked = MockSock::default();
-
-            mocked.expect_write_message().returning(|_| Ok(()));
-
-            mocked
-                .expect_read_message()
-                .returning(|| bail!("Failed to write"));
-
-            mocked.expect_close().once();
-            Ok(mocked)
-        });
-
-        let provider = Bitstamp::new(Config::as_ref());
+        let (provider, memory) = memory();
+        memory.push_error("Failed to read");
 
         assert!(provider.subscribe().is_err());
     }
 
     #[test]
     fn test_unsubscribe_well() {
-        let context = MockSock::new_context();
-
-        context.expect().returning(|_url| {
-            let mut mocked = MockSock::default();
-
-            mocked
-                .expect_write_message()
-                .withf(|message| {
-                    message.to_string().eq("{\"event\":\"bts:unsubscribe\",\"data\":{\"channel\":\"order_book_ethbtc\"}}")
-                })
-                .returning(|_| Ok(()));
-
-            mocked
-                .expect_read_message()
-                .returning(|| Ok(Message::Text(String::from("{{}}"))));
-
-            mocked.expect_close().once();
-            Ok(mocked)
-        });
-
-        let provider = Bitstamp::new(Config::as_ref());
+        let (provider, memory) = memory();
+        memory.push_text("{{}}");
 
         assert!(provider.unsubscribe().is_ok());
+        assert_eq!(
+            memory.sent(),
+            [Message::Text(String::from(
+                "{\"event\":\"bts:unsubscribe\",\"data\":{\"channel\":\"order_book_ethbtc\"}}"
+            ))]
+        );
     }
 
     #[test]
     fn test_summary_well() -> Result<()> {
-        let context = MockSock::new_context();
-
-        context.expect().returning(|_url| {
-            let mut mocked = MockSock::default();
-
-            mocked.expect_read_message().returning(|| {
-                Ok(Message::Text(String::from(
-                    r#"{"data":{"timestamp":"1682624742","microtimestamp":"1682624742462361","bids":[["0.06466182","0.50000000"],["0.06465586","0.77986816"]],"asks":[["0.06468051","0.50000000"],["0.06468374","0.40000000"]]},"channel":"","event":""}"#,
-                )))
-            });
-
-            mocked.expect_close().once();
-            Ok(mocked)
-        });
-
-        let provider = Bitstamp::new(Config::as_ref());
+        let (provider, memory) = memory();
+        memory.push_text(
+            r#"{"event":"bts:subscription_succeeded","channel":"order_book_ethbtc","data":{}}"#,
+        );
+        memory.push_text(
+            r#"{"data":{"timestamp":"1682624742","microtimestamp":"1682624742462361","bids":[["0.06466182","0.50000000"],["0.06465586","0.77986816"]],"asks":[["0.06468051","0.50000000"],["0.06468374","0.40000000"]]},"channel":"order_book_ethbtc","event":"data"}"#,
+        );
 
         let summary = provider.summary()?;
 
+        assert_eq!(summary.event_timestamp, 1682624742462361);
+        assert!(summary.receive_timestamp > summary.event_timestamp);
+
         assert_eq!(summary.bids.len(), 2);
 
         assert_eq!(summary.bids[0].exchange, "Bitstamp");
@@ -301,4 +268,34 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_summary_fail() {
+        let (provider, memory) = memory();
+        memory.push_text(r#"{"data":{"timestamp":"1","microtimestamp":"x","bids":[],"asks":[]},"channel":"order_book_ethbtc","event":"data"}"#);
+        memory.push_error("Failed to read");
+
+        assert!(provider.summary().is_err());
+        assert_eq!(
+            provider.summary().unwrap_err().to_string(),
+            "Failed to read"
+        );
+    }
+
+    #[test]
+    fn test_malformed_data_fails() {
+        let received = 1_682_624_742_500_000;
+
+        // no bids, a numeric price, a level of three
+        for frame in [
+            r#"{"data":{"timestamp":"1","microtimestamp":"1","asks":[]},"channel":"order_book_ethbtc","event":"data"}"#,
+            r#"{"data":{"timestamp":"1","microtimestamp":"1","bids":[[0.06,"1"]],"asks":[]},"channel":"order_book_ethbtc","event":"data"}"#,
+            r#"{"data":{"timestamp":"1","microtimestamp":"1","bids":[["0.06","1","2"]],"asks":[]},"channel":"order_book_ethbtc","event":"data"}"#,
+        ] {
+            assert!(parse(frame, received).is_err(), "{}", frame);
+        }
+
+        let other = r#"{"event":"bts:request_reconnect","channel":"","data":""}"#;
+        assert!(matches!(parse(other, received), Ok(None)));
+    }
 }
@@ -1,18 +1,26 @@
 use crate::orderbook::OrderBook;
 use serde::Deserialize;
 
+/// Frame dispatched on its `event`, an order book must parse in full
 #[derive(Deserialize)]
-pub struct Response {
-    data: OrderBook,
-    #[serde(rename = "channel")]
-    _channel: String,
-
-    #[serde(rename = "event")]
-    _event: String,
+#[serde(tag = "event")]
+pub enum Response {
+    #[serde(rename = "data")]
+    Data {
+        data: OrderBook,
+        #[serde(rename = "channel")]
+        _channel: String,
+    },
+    /// Subscription acknowledges and reconnect requests
+    #[serde(other)]
+    Other,
 }
 
 impl Response {
-    pub fn orderbook(&self) -> &OrderBook {
-        &self.data
+    pub fn orderbook(&self) -> Option<&OrderBook> {
+        match self {
+            Response::Data { data, .. } => Some(data),
+            Response::Other => None,
+        }
     }
 }
@@ -0,0 +1,117 @@
+use crate::orderbook::{Level, Summary};
+use std::{cmp::Ordering, collections::BTreeMap};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Bid,
+    Ask,
+}
+
+/// Local copy of a venue book, needed when the feed only publishes deltas
+#[derive(Default)]
+pub struct Book {
+    bids: BTreeMap<Price, f64>,
+    asks: BTreeMap<Price, f64>,
+}
+
+impl Book {
+    pub fn clear(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+
+    /// Sets the amount resting at a price, a zero amount removes the level
+    pub fn update(&mut self, side: Side, price: f64, amount: f64) {
+        let levels = match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        };
+
+        if amount == 0.0 {
+            levels.remove(&Price(price));
+        } else {
+            levels.insert(Price(price), amount);
+        }
+    }
+
+    /// Every level of each side, asks ascending and bids descending
+    pub fn summary(&self, exchange: &str) -> Summary {
+        let level = |(price, amount): (&Price, &f64)| Level {
+            exchange: String::from(exchange),
+            price: price.0,
+            amount: *amount,
+            ..Default::default()
+        };
+
+        Summary {
+            asks: self.asks.iter().map(level).collect(),
+            bids: self.bids.iter().rev().map(level).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Price(f64);
+
+impl PartialEq for Price {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_and_summary() {
+        let mut book = Book::default();
+
+        book.update(Side::Ask, 0.0648, 1.0);
+        book.update(Side::Ask, 0.0647, 2.0);
+        book.update(Side::Ask, 0.0649, 3.0);
+        book.update(Side::Bid, 0.0645, 4.0);
+        book.update(Side::Bid, 0.0646, 5.0);
+        book.update(Side::Ask, 0.0648, 0.0);
+        book.update(Side::Bid, 0.0645, 6.0);
+
+        let summary = book.summary("Test");
+
+        let asks: Vec<(f64, f64)> = summary.asks.iter().map(|l| (l.price, l.amount)).collect();
+        let bids: Vec<(f64, f64)> = summary.bids.iter().map(|l| (l.price, l.amount)).collect();
+
+        assert_eq!(asks, vec![(0.0647, 2.0), (0.0649, 3.0)]);
+        assert_eq!(bids, vec![(0.0646, 5.0), (0.0645, 6.0)]);
+        assert_eq!(summary.asks[0].exchange, "Test");
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut book = Book::default();
+
+        book.update(Side::Bid, 0.0645, 4.0);
+        assert!(!book.is_empty());
+
+        book.clear();
+        assert!(book.is_empty());
+    }
+}
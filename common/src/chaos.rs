@@ -0,0 +1,445 @@
+use crate::{
+    config::{Config, Faults},
+    orderbook::Summary,
+    Provider, Transport,
+};
+use anyhow::{anyhow, bail, Result};
+use log::{debug, info};
+use parking_lot::Mutex;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+use tungstenite::Message;
+use url::Url;
+
+/// Decides which faults hit the frames read through it
+struct Injector<T> {
+    faults: Faults,
+    state: Mutex<State<T>>,
+    /// Outside the state, which is held while blocked on a read
+    disconnected: AtomicBool,
+}
+
+struct State<T> {
+    rng: ChaCha8Rng,
+    /// Duplicated or reordered frames waiting to be delivered
+    pending: VecDeque<T>,
+}
+
+impl<T: Clone> Injector<T> {
+    fn new(faults: Faults) -> Self {
+        let seed = faults.seed.unwrap_or_else(rand::random);
+        info!("chaos seed {}", seed);
+
+        Self {
+            faults,
+            state: Mutex::new(State {
+                rng: ChaCha8Rng::seed_from_u64(seed),
+                pending: VecDeque::new(),
+            }),
+            disconnected: AtomicBool::new(false),
+        }
+    }
+
+    fn check(&self) -> Result<()> {
+        if self.disconnected.load(Ordering::Acquire) {
+            bail!("chaos disconnect");
+        }
+        Ok(())
+    }
+
+    /// Lifts an injected disconnect, the way subscribing again recovers a dropped feed
+    fn reset(&self) {
+        self.disconnected.store(false, Ordering::Release);
+    }
+
+    /// Next frame from `read` once the faults are applied, `corrupt` cuts a frame short
+    fn next<R, C>(&self, mut read: R, corrupt: C) -> Result<T>
+    where
+        R: FnMut() -> Result<T>,
+        C: FnOnce(T) -> Result<T>,
+    {
+        self.check()?;
+        let mut state = self.state.lock();
+
+        if !self.faults.latency.is_zero() {
+            let latency = state
+                .rng
+                .gen_range(0..=self.faults.latency.as_millis() as u64);
+            thread::sleep(Duration::from_millis(latency));
+        }
+
+        if let Some(frame) = state.pending.pop_front() {
+            return Ok(frame);
+        }
+
+        loop {
+            if chance(&mut state.rng, self.faults.disconnect) {
+                debug!("chaos disconnect");
+                self.disconnected.store(true, Ordering::Release);
+                bail!("chaos disconnect");
+            }
+
+            let mut frame = read()?;
+
+            if chance(&mut state.rng, self.faults.drop) {
+                debug!("chaos drop");
+                continue;
+            }
+            if chance(&mut state.rng, self.faults.duplicate) {
+                debug!("chaos duplicate");
+                state.pending.push_back(frame.clone());
+            }
+            if chance(&mut state.rng, self.faults.reorder) {
+                debug!("chaos reorder");
+                state.pending.push_back(frame);
+                frame = read()?;
+            }
+            if chance(&mut state.rng, self.faults.truncate) {
+                debug!("chaos truncate");
+                return corrupt(frame);
+            }
+
+            return Ok(frame);
+        }
+    }
+}
+
+/// Whether a fault with probability `p` hits, out of range chances never panic
+fn chance(rng: &mut ChaCha8Rng, p: f64) -> bool {
+    p > 0.0 && rng.gen::<f64>() < p
+}
+
+/// Transport whose received frames suffer the configured faults, a new connection starts without
+/// the disconnect of the last one
+pub struct ChaosTransport<T: Transport> {
+    inner: T,
+    injector: Injector<Message>,
+}
+
+impl<T: Transport> ChaosTransport<T> {
+    pub fn new(inner: T, faults: Faults) -> Self {
+        Self {
+            inner,
+            injector: Injector::new(faults),
+        }
+    }
+}
+
+impl<T: Transport> Transport for ChaosTransport<T> {
+    /// Connection suffering the faults of the configuration
+    fn connect(config: &Config, url: &Url) -> Result<Self> {
+        Ok(Self::new(T::connect(config, url)?, config.faults()))
+    }
+
+    fn send(&self, message: Message) -> Result<()> {
+        self.injector.check()?;
+        self.inner.send(message)
+    }
+
+    fn receive(&self) -> Result<Message> {
+        self.injector.next(|| self.inner.receive(), truncate)
+    }
+
+    fn ping(&self) -> Result<()> {
+        self.injector.check()?;
+        self.inner.ping()
+    }
+
+    fn close(&self) {
+        self.inner.close()
+    }
+}
+
+/// Half of the payload, the way a frame cut mid-JSON looks
+fn truncate(message: Message) -> Result<Message> {
+    match message {
+        Message::Text(text) => {
+            let mut end = text.len() / 2;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            Ok(Message::Text(String::from(&text[..end])))
+        }
+        Message::Binary(data) => Ok(Message::Binary(data[..data.len() / 2].to_vec())),
+        other => Ok(other),
+    }
+}
+
+/// The feed, with its summaries suffering the configured faults when chaos is on for the exchange
+pub fn feed(config: &Config, name: &str, provider: Box<dyn Provider>) -> Box<dyn Provider> {
+    match config.chaos(name) {
+        true => Box::new(ChaosProvider::new(provider, config.faults())),
+        false => provider,
+    }
+}
+
+/// Provider whose summaries suffer the configured faults, for feeds not built on a `Transport`
+///
+/// Summaries are already parsed, a truncated one surfaces as the parse error it would have caused.
+pub struct ChaosProvider {
+    inner: Box<dyn Provider>,
+    injector: Injector<Summary>,
+}
+
+impl ChaosProvider {
+    pub fn new(inner: Box<dyn Provider>, faults: Faults) -> Self {
+        Self {
+            inner,
+            injector: Injector::new(faults),
+        }
+    }
+}
+
+impl Provider for ChaosProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn subscribe(&self) -> Result<()> {
+        self.injector.reset();
+        self.inner.subscribe()
+    }
+
+    fn unsubscribe(&self) -> Result<()> {
+        self.injector.check()?;
+        self.inner.unsubscribe()
+    }
+
+    fn summary(&self) -> Result<Summary> {
+        self.injector.next(
+            || self.inner.summary(),
+            |_| Err(anyhow!("chaos truncated frame")),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::Memory;
+    use std::{sync::Arc, time::Instant};
+
+    fn seeded() -> Faults {
+        Faults {
+            seed: Some(1),
+            ..Default::default()
+        }
+    }
+
+    fn chaos(faults: Faults, frames: &[&str]) -> ChaosTransport<Arc<Memory>> {
+        let memory = Arc::new(Memory::default());
+        for frame in frames {
+            memory.push_text(frame);
+        }
+        ChaosTransport::new(memory, faults)
+    }
+
+    fn text(transport: &impl Transport) -> Result<String> {
+        Ok(transport.receive()?.into_text()?)
+    }
+
+    #[test]
+    fn test_passthrough() -> Result<()> {
+        let transport = chaos(seeded(), &["1", "2"]);
+
+        assert_eq!(text(&transport)?, "1");
+        assert_eq!(text(&transport)?, "2");
+        assert!(transport.receive().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_drop() {
+        let faults = Faults {
+            drop: 1.0,
+            ..seeded()
+        };
+        let transport = chaos(faults, &["1", "2"]);
+
+        // every frame dropped until the script runs out
+        assert!(transport.receive().is_err());
+    }
+
+    #[test]
+    fn test_duplicate() -> Result<()> {
+        let faults = Faults {
+            duplicate: 1.0,
+            ..seeded()
+        };
+        let transport = chaos(faults, &["1", "2"]);
+
+        assert_eq!(text(&transport)?, "1");
+        assert_eq!(text(&transport)?, "1");
+        assert_eq!(text(&transport)?, "2");
+        assert_eq!(text(&transport)?, "2");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reorder() -> Result<()> {
+        let faults = Faults {
+            reorder: 1.0,
+            ..seeded()
+        };
+        let transport = chaos(faults, &["1", "2", "3", "4"]);
+
+        assert_eq!(text(&transport)?, "2");
+        assert_eq!(text(&transport)?, "1");
+        assert_eq!(text(&transport)?, "4");
+        assert_eq!(text(&transport)?, "3");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncate() -> Result<()> {
+        let faults = Faults {
+            truncate: 1.0,
+            ..seeded()
+        };
+        let transport = chaos(faults, &[r#"{"bids":[],"asks":[]}"#]);
+
+        let frame = text(&transport)?;
+        assert_eq!(frame, r#"{"bids":[]"#);
+        assert!(serde_json::from_str::<serde_json::Value>(&frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_disconnect() {
+        let faults = Faults {
+            disconnect: 1.0,
+            ..seeded()
+        };
+        let transport = chaos(faults, &["1"]);
+
+        assert!(transport.receive().is_err());
+        assert!(transport.send(Message::Text(String::from("ping"))).is_err());
+        assert!(transport.ping().is_err());
+        assert!(transport.receive().is_err());
+    }
+
+    #[test]
+    fn test_latency() -> Result<()> {
+        let faults = Faults {
+            latency: Duration::from_millis(20),
+            ..seeded()
+        };
+        let transport = chaos(faults, &["1"; 10]);
+
+        let start = Instant::now();
+        for _ in 0..10 {
+            text(&transport)?;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        Ok(())
+    }
+
+    struct Counter(Mutex<u64>);
+
+    impl Provider for Counter {
+        fn name(&self) -> &'static str {
+            "Counter"
+        }
+
+        fn subscribe(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn unsubscribe(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn summary(&self) -> Result<Summary> {
+            let mut count = self.0.lock();
+            *count += 1;
+            Ok(Summary {
+                event_timestamp: *count,
+                ..Default::default()
+            })
+        }
+    }
+
+    #[test]
+    fn test_provider() -> Result<()> {
+        let faults = Faults {
+            reorder: 1.0,
+            ..seeded()
+        };
+        let provider = ChaosProvider::new(Box::new(Counter(Mutex::new(0))), faults);
+
+        assert_eq!(provider.name(), "Counter");
+        assert_eq!(provider.summary()?.event_timestamp, 2);
+        assert_eq!(provider.summary()?.event_timestamp, 1);
+
+        let faults = Faults {
+            truncate: 1.0,
+            ..seeded()
+        };
+        let provider = ChaosProvider::new(Box::new(Counter(Mutex::new(0))), faults);
+        assert!(provider.summary().is_err());
+        assert!(provider.summary().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_provider_resubscribe() -> Result<()> {
+        let faults = Faults {
+            disconnect: 1.0,
+            ..seeded()
+        };
+        let provider = ChaosProvider::new(Box::new(Counter(Mutex::new(0))), faults);
+
+        assert!(provider.summary().is_err());
+        assert!(provider.unsubscribe().is_err());
+        assert!(provider.subscribe().is_ok());
+        assert!(provider.unsubscribe().is_ok());
+
+        // out of range chances from a configuration that skipped validation
+        let faults = Faults {
+            disconnect: 2.0,
+            drop: f64::NAN,
+            ..seeded()
+        };
+        let provider = ChaosProvider::new(Box::new(Counter(Mutex::new(0))), faults);
+        assert!(provider.summary().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_configured() -> Result<()> {
+        let config = Config::load_from([
+            "algo",
+            "--chaos",
+            "counter",
+            "--chaos-seed",
+            "1",
+            "--chaos-truncate",
+            "1",
+        ])?;
+
+        let transport = ChaosTransport::<Arc<Memory>>::connect(&config, &Url::parse("wss://x")?)?;
+        transport.inner.push_text(r#"{"bids":[],"asks":[]}"#);
+        assert_eq!(text(&transport)?, r#"{"bids":[]"#);
+
+        let provider = feed(&config, "Counter", Box::new(Counter(Mutex::new(0))));
+        assert!(provider.summary().is_err());
+
+        let provider = feed(&config, "Other", Box::new(Counter(Mutex::new(0))));
+        assert!(provider.summary().is_ok());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,16 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Wall clock in unix microseconds, the unit of the `Summary` timestamps
+pub fn micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_micros() as u64)
+}
+
+/// Microseconds from `from` to `to`, none when either is unknown, clamped at zero on clock skew
+pub fn elapsed(from: u64, to: u64) -> Option<u64> {
+    match (from, to) {
+        (0, _) | (_, 0) => None,
+        _ => Some(to.saturating_sub(from)),
+    }
+}
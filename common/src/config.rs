@@ -1,67 +1,705 @@
-use clap::Parser;
-use std::{net::SocketAddr, sync::Arc};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{parser::ValueSource, ArgMatches, CommandFactory, Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fmt, fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
 
 pub type ConfigRef = Arc<Config>;
 
 /// it's just an assessment
-#[derive(Parser)]
+///
+/// Layered from lowest to highest precedence: defaults, `--config` file, `ALGO_*` environment, command line.
+#[derive(Parser, Clone, Serialize)]
 #[command(author, version, about, long_about = None)]
 pub struct Config {
+    /// Configuration file, TOML or YAML
+    #[arg(long, env = "ALGO_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Reload when the configuration file changes, SIGHUP always reloads
+    #[arg(long, env = "ALGO_WATCH_CONFIG", default_value_t = false)]
+    watch_config: bool,
+
+    /// Validate and print the effective configuration, then exit
+    #[arg(long, env = "ALGO_CHECK_CONFIG", default_value_t = false)]
+    #[serde(skip)]
+    check_config: bool,
+
     /// Pair
-    #[arg(long, default_value = "ethbtc")]
+    #[arg(long, env = "ALGO_PAIR", default_value = "ethbtc")]
     pair: String,
 
-    #[cfg(feature = "binance")]
+    /// Exchanges to connect, by registered name
+    #[arg(
+        long,
+        env = "ALGO_EXCHANGES",
+        value_delimiter = ',',
+        default_value = "binance,bitstamp"
+    )]
+    exchanges: Vec<String>,
+
     /// Binance URL
     #[arg(
         long,
+        env = "ALGO_BINANCE_URL",
         value_parser(url::Url::parse),
         default_value = "wss://stream.binance.com:9443/ws/"
     )]
     binance_url: url::Url,
 
-    #[cfg(feature = "bitstamp")]
     /// Bitstamp URL
     #[arg(
         long,
+        env = "ALGO_BITSTAMP_URL",
         value_parser(url::Url::parse),
         default_value = "wss://ws.bitstamp.net"
     )]
     bitstamp_url: url::Url,
 
-    #[arg(long, default_value_t = 10)]
+    /// Gemini URL
+    #[arg(
+        long,
+        env = "ALGO_GEMINI_URL",
+        value_parser(url::Url::parse),
+        default_value = "wss://api.gemini.com/v2/marketdata"
+    )]
+    gemini_url: url::Url,
+
+    /// Generic exchange definition, repeat for several exchanges
+    #[arg(long, env = "ALGO_GENERIC_DEFINITION", value_delimiter = ',')]
+    generic_definition: Vec<PathBuf>,
+
+    /// HTX URL
+    #[arg(
+        long,
+        env = "ALGO_HTX_URL",
+        value_parser(url::Url::parse),
+        default_value = "wss://api.huobi.pro/ws"
+    )]
+    htx_url: url::Url,
+
+    /// KuCoin REST URL, the websocket endpoint is handed out by the bullet
+    #[arg(
+        long,
+        env = "ALGO_KUCOIN_URL",
+        value_parser(url::Url::parse),
+        default_value = "https://api.kucoin.com"
+    )]
+    kucoin_url: url::Url,
+
+    #[arg(long, env = "ALGO_TOP", default_value_t = 10)]
     /// Top rows
     top: usize,
 
-    #[arg(long, default_value = "[::1]:50051")]
+    #[arg(long, env = "ALGO_LOCAL_BIND", default_value = "[::1]:50051")]
     // Local bind
     local_bind: SocketAddr,
 
-    #[arg(long, default_value_t = false)]
+    /// Publish the latency timestamps in every summary
+    #[arg(long, env = "ALGO_SUMMARY_TIMESTAMPS", default_value_t = false)]
+    summary_timestamps: bool,
+
+    /// Prometheus `/metrics` bind
+    #[arg(long, env = "ALGO_METRICS_BIND", default_value = "[::1]:9184")]
+    metrics_bind: SocketAddr,
+
+    /// Exchanges whose received frames are captured to disk
+    #[arg(long, env = "ALGO_RECORD", value_delimiter = ',')]
+    record: Vec<String>,
+
+    /// Directory of the capture files
+    #[arg(long, env = "ALGO_RECORD_DIR", default_value = "capture")]
+    record_dir: PathBuf,
+
+    /// Uncompressed bytes after which a capture file is rotated
+    #[arg(long, env = "ALGO_RECORD_MAX_BYTES", default_value_t = 64 * 1024 * 1024)]
+    record_max_bytes: u64,
+
+    /// Seconds after which a capture file is rotated
+    #[arg(long, env = "ALGO_RECORD_MAX_AGE", default_value_t = 3600)]
+    record_max_age: u64,
+
+    /// Capture files or directories played back in place of the live exchanges
+    #[arg(long, env = "ALGO_REPLAY", value_delimiter = ',')]
+    replay: Vec<PathBuf>,
+
+    /// Playback pace: `realtime`, a speed-up factor such as `10`, or `max`
+    #[arg(long, env = "ALGO_REPLAY_SPEED", default_value = "realtime")]
+    #[serde(serialize_with = "display")]
+    replay_speed: Speed,
+
+    /// Seed of the simulator, a random one is logged when unset
+    #[arg(long, env = "ALGO_SIMULATOR_SEED")]
+    simulator_seed: Option<u64>,
+
+    /// Mid price the simulator starts from
+    #[arg(long, env = "ALGO_SIMULATOR_MID", default_value_t = 0.065)]
+    simulator_mid: f64,
+
+    /// Simulated spread, in basis points of the mid
+    #[arg(long, env = "ALGO_SIMULATOR_SPREAD", default_value_t = 2.0)]
+    simulator_spread: f64,
+
+    /// Distance between simulated levels, in basis points of the mid
+    #[arg(long, env = "ALGO_SIMULATOR_TICK", default_value_t = 1.0)]
+    simulator_tick: f64,
+
+    /// Standard deviation of each move of the simulated mid, in basis points
+    #[arg(long, env = "ALGO_SIMULATOR_VOLATILITY", default_value_t = 1.0)]
+    simulator_volatility: f64,
+
+    /// Amount simulated at the top level
+    #[arg(long, env = "ALGO_SIMULATOR_AMOUNT", default_value_t = 1.0)]
+    simulator_amount: f64,
+
+    /// Amount added by each deeper simulated level, as a fraction of the top one
+    #[arg(long, env = "ALGO_SIMULATOR_GROWTH", default_value_t = 0.5)]
+    simulator_growth: f64,
+
+    /// Milliseconds between simulated updates, zero for as fast as they are read
+    #[arg(long, env = "ALGO_SIMULATOR_INTERVAL", default_value_t = 100)]
+    simulator_interval: u64,
+
+    /// Chance of each simulated update starting a liquidity shock
+    #[arg(long, env = "ALGO_SIMULATOR_SHOCK", default_value_t = 0.01)]
+    simulator_shock: f64,
+
+    /// Chance of each simulated update being crossed
+    #[arg(long, env = "ALGO_SIMULATOR_CROSS", default_value_t = 0.005)]
+    simulator_cross: f64,
+
+    /// What happens to a book with nonsense levels or crossed on its own
+    #[arg(long, env = "ALGO_VALIDATION", value_enum, default_value_t = Policy::DropLevel)]
+    validation: Policy,
+
+    /// Seconds a quarantined exchange stays out of the merge
+    #[arg(long, env = "ALGO_QUARANTINE", default_value_t = 60)]
+    quarantine: u64,
+
+    /// Ranks merged levels by their price after the taker fee of `[exchange.<name>.fees]`
+    #[arg(long, env = "ALGO_FEE_ADJUSTED", default_value_t = false)]
+    fee_adjusted: bool,
+
+    /// Mid-price estimators published on the price stream
+    #[arg(
+        long,
+        env = "ALGO_PRICING",
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = [Estimator::Mid, Estimator::Micro, Estimator::Depth, Estimator::Exchange]
+    )]
+    pricing: Vec<Estimator>,
+
+    /// Basis points from the best bid and best ask whose levels the depth-weighted mid takes in
+    #[arg(long, env = "ALGO_PRICING_BAND", default_value_t = 10.0)]
+    pricing_band: f64,
+
+    /// Basis points left after both taker fees before a bid crossing another exchange's ask counts as arbitrage
+    #[arg(long, env = "ALGO_ARBITRAGE_EDGE", default_value_t = 0.0)]
+    arbitrage_edge: f64,
+
+    /// Ended arbitrage windows replayed to a new client of the arbitrage stream
+    #[arg(long, env = "ALGO_ARBITRAGE_HISTORY", default_value_t = 100)]
+    arbitrage_history: usize,
+
+    /// Basis points an exchange mid may stray from the median of the others, zero disables the breaker
+    #[arg(long, env = "ALGO_BREAKER_THRESHOLD", default_value_t = 500.0)]
+    breaker_threshold: f64,
+
+    /// Seconds an exchange must stray before it is left out of the merge
+    #[arg(long, env = "ALGO_BREAKER_WINDOW", default_value_t = 5)]
+    breaker_window: u64,
+
+    /// Exchanges whose feed goes through fault injection, for testing and staging only
+    #[arg(long, env = "ALGO_CHAOS", value_delimiter = ',')]
+    chaos: Vec<String>,
+
+    /// Seed of the fault injection, a random one is logged when unset
+    #[arg(long, env = "ALGO_CHAOS_SEED")]
+    chaos_seed: Option<u64>,
+
+    /// Milliseconds of latency added to each frame at most
+    #[arg(long, env = "ALGO_CHAOS_LATENCY", default_value_t = 0)]
+    chaos_latency: u64,
+
+    /// Chance of a frame being dropped
+    #[arg(long, env = "ALGO_CHAOS_DROP", default_value_t = 0.0)]
+    chaos_drop: f64,
+
+    /// Chance of a frame being delivered twice
+    #[arg(long, env = "ALGO_CHAOS_DUPLICATE", default_value_t = 0.0)]
+    chaos_duplicate: f64,
+
+    /// Chance of a frame swapping places with the next one
+    #[arg(long, env = "ALGO_CHAOS_REORDER", default_value_t = 0.0)]
+    chaos_reorder: f64,
+
+    /// Chance of a frame being cut short
+    #[arg(long, env = "ALGO_CHAOS_TRUNCATE", default_value_t = 0.0)]
+    chaos_truncate: f64,
+
+    /// Chance of the connection dying before a frame
+    #[arg(long, env = "ALGO_CHAOS_DISCONNECT", default_value_t = 0.0)]
+    chaos_disconnect: f64,
+
+    /// Log lines as plain text or one JSON object each
+    #[arg(long, env = "ALGO_LOG_FORMAT", value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// OTLP collector receiving the traces, none exports nothing
+    #[arg(long, env = "ALGO_OTLP_ENDPOINT", value_parser(url::Url::parse))]
+    otlp_endpoint: Option<url::Url>,
+
+    /// Seconds to wait for clients to drain on shutdown
+    #[arg(long, env = "ALGO_DRAIN_TIMEOUT", default_value_t = 10)]
+    drain_timeout: u64,
+
+    #[arg(long, env = "ALGO_CLI", default_value_t = false)]
     // CLI
     cli: bool,
+
+    /// Per exchange sections, only read from the configuration file
+    #[arg(skip)]
+    exchange: BTreeMap<String, Exchange>,
+}
+
+/// Pace of a replay relative to the capture
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Speed {
+    Realtime,
+    Times(f64),
+    Max,
+}
+
+impl FromStr for Speed {
+    type Err = anyhow::Error;
+
+    fn from_str(speed: &str) -> Result<Self> {
+        match speed {
+            "realtime" => Ok(Speed::Realtime),
+            "max" => Ok(Speed::Max),
+            factor => match factor.parse::<f64>() {
+                Ok(factor) if factor > 0.0 && factor.is_finite() => Ok(Speed::Times(factor)),
+                _ => bail!("speed must be realtime, max or a positive factor"),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Speed::Realtime => write!(f, "realtime"),
+            Speed::Times(factor) => write!(f, "{}", factor),
+            Speed::Max => write!(f, "max"),
+        }
+    }
+}
+
+/// Faults injected into a feed, see the `chaos_*` arguments
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Faults {
+    pub seed: Option<u64>,
+    pub latency: Duration,
+    pub drop: f64,
+    pub duplicate: f64,
+    pub reorder: f64,
+    pub truncate: f64,
+    pub disconnect: f64,
+}
+
+/// Settings of the simulated exchange, see the `simulator_*` arguments
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Simulation {
+    pub seed: Option<u64>,
+    pub mid: f64,
+    pub spread: f64,
+    pub tick: f64,
+    pub volatility: f64,
+    pub amount: f64,
+    pub growth: f64,
+    pub interval: Duration,
+    pub shock: f64,
+    pub cross: f64,
+}
+
+/// Fee schedule of an exchange in basis points, `[exchange.<name>.fees]` of the configuration file
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fees {
+    #[serde(default)]
+    pub maker: f64,
+    #[serde(default)]
+    pub taker: f64,
+    /// Trailing 30 day volume of the account, picks the tier
+    #[serde(default)]
+    pub volume: f64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tiers: Vec<Tier>,
+}
+
+/// Rates from a volume on, replacing the base ones of the schedule
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tier {
+    pub volume: f64,
+    pub maker: f64,
+    pub taker: f64,
+}
+
+impl Fees {
+    pub fn maker(&self) -> f64 {
+        self.tier().map_or(self.maker, |tier| tier.maker)
+    }
+
+    pub fn taker(&self) -> f64 {
+        self.tier().map_or(self.taker, |tier| tier.taker)
+    }
+
+    /// Highest tier the volume reaches
+    fn tier(&self) -> Option<&Tier> {
+        self.tiers
+            .iter()
+            .filter(|tier| tier.volume <= self.volume)
+            .max_by(|x, y| x.volume.total_cmp(&y.volume))
+    }
+}
+
+/// Validation policy, a crossed book has no level to single out so it is always dropped whole
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Policy {
+    /// Merges the book without the offending levels
+    DropLevel,
+    /// Leaves the book out of this merge
+    DropFrame,
+    /// Leaves the exchange out of the merge for `quarantine` seconds
+    Quarantine,
+}
+
+/// Mid-price estimator, see the `Prices` message
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Estimator {
+    /// Halfway between the best bid and ask
+    Mid,
+    /// Best bid and ask weighted by the amount across
+    Micro,
+    /// Levels within `pricing_band` of the best bid and best ask, weighted by the depth across
+    Depth,
+    /// Mids of the exchanges weighted by their section `weight`
+    Exchange,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+/// `[exchange.<name>]` section of the configuration file
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Exchange {
+    /// Replaces `<name>_url`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<url::Url>,
+    /// Levels kept from each update
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<usize>,
+    /// Milliseconds between updates, for venues that let you choose
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_speed: Option<u64>,
+    /// Adds or removes the exchange from `exchanges`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Adds or removes the exchange from `record`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record: Option<bool>,
+    /// Adds or removes the exchange from `chaos`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chaos: Option<bool>,
+    /// Replaces `validation`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation: Option<Policy>,
+    /// Maker and taker fees, zero when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fees: Option<Fees>,
+    /// Share in the exchange-weighted mid, one when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f64>,
 }
 
 impl Config {
+    /// Defaults and `ALGO_*` environment only, the command line is not read
     pub fn as_ref() -> ConfigRef {
         let def: Vec<std::ffi::OsString> = vec![];
         Arc::new(Self::parse_from(def))
     }
 
+    /// Every layer, reading the process command line
+    pub fn load() -> Result<ConfigRef> {
+        match Self::load_from(std::env::args_os()) {
+            Ok(config) => Ok(Arc::new(config)),
+            // --help, --version and usage errors print and exit like a plain parse
+            Err(e) => match e.downcast::<clap::Error>() {
+                Ok(e) => e.exit(),
+                Err(e) => Err(e),
+            },
+        }
+    }
+
+    pub fn load_from<I, T>(args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+        let matches = Self::command().try_get_matches_from(args.iter())?;
+
+        let file = match matches.get_one::<PathBuf>("config") {
+            Some(path) => read(path)?,
+            None => return Ok(Self::try_parse_from(args)?),
+        };
+
+        Self::layer(args, &matches, file)
+    }
+
+    /// Every layer, with `text` standing for the TOML configuration file
+    pub fn load_toml<I, T>(text: &str, args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+        let matches = Self::command().try_get_matches_from(args.iter())?;
+
+        let file = match toml::from_str(text)? {
+            Value::Object(map) => map,
+            _ => bail!("configuration is not a table"),
+        };
+
+        Self::layer(args, &matches, file)
+    }
+
+    fn layer(args: Vec<OsString>, matches: &ArgMatches, file: Map<String, Value>) -> Result<Self> {
+        // file values become arguments for whatever the environment and command line left at default
+        let mut layered = args;
+        layered.extend(file_args(&file, matches)?);
+
+        let mut config = Self::try_parse_from(layered)?;
+
+        if let Some(sections) = file.get("exchange") {
+            config.exchange = serde_json::from_value(sections.clone())
+                .with_context(|| "Invalid exchange section")?;
+        }
+
+        if defaulted(matches, "exchanges") {
+            for (name, section) in config.exchange.iter() {
+                match section.enabled {
+                    Some(true) if !config.exchanges.contains(name) => {
+                        config.exchanges.push(name.clone())
+                    }
+                    Some(false) => config.exchanges.retain(|exchange| exchange != name),
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Errors a deployment would only find once connected
+    pub fn validate(&self) -> Result<()> {
+        if self.pair.is_empty() {
+            bail!("pair is empty");
+        }
+        if self.top == 0 {
+            bail!("top must be positive");
+        }
+        if self.exchanges.is_empty() {
+            bail!("no exchange enabled");
+        }
+
+        if self.simulator_mid <= 0.0 {
+            bail!("simulator_mid must be positive");
+        }
+        if self.simulator_spread < 0.0 || self.simulator_tick <= 0.0 {
+            bail!("simulator_spread must not be negative and simulator_tick must be positive");
+        }
+        if self.simulator_volatility < 0.0
+            || self.simulator_amount <= 0.0
+            || self.simulator_growth < 0.0
+        {
+            bail!(
+                "simulator_volatility, simulator_amount and simulator_growth must not be negative"
+            );
+        }
+        if !(0.0..=1.0).contains(&self.simulator_shock)
+            || !(0.0..=1.0).contains(&self.simulator_cross)
+        {
+            bail!("simulator_shock and simulator_cross are probabilities");
+        }
+        for (name, fees) in self
+            .exchange
+            .iter()
+            .filter_map(|(name, section)| Some((name, section.fees.as_ref()?)))
+        {
+            let rates = fees
+                .tiers
+                .iter()
+                .flat_map(|tier| [tier.maker, tier.taker])
+                .chain([fees.maker, fees.taker]);
+            for rate in rates {
+                if !(-10_000.0..10_000.0).contains(&rate) {
+                    bail!("exchange.{}.fees must be below 10000 bps", name);
+                }
+            }
+        }
+        if self
+            .exchange
+            .values()
+            .filter_map(|section| section.weight)
+            .any(|weight| weight < 0.0)
+        {
+            bail!("exchange weights must not be negative");
+        }
+        if self.pricing_band <= 0.0 {
+            bail!("pricing_band must be positive");
+        }
+        if self.breaker_threshold < 0.0 {
+            bail!("breaker_threshold must not be negative");
+        }
+        for chance in [
+            self.chaos_drop,
+            self.chaos_duplicate,
+            self.chaos_reorder,
+            self.chaos_truncate,
+            self.chaos_disconnect,
+        ] {
+            if !(0.0..=1.0).contains(&chance) {
+                bail!("chaos_* chances are probabilities");
+            }
+        }
+
+        for (name, section) in self.exchange.iter() {
+            if section.depth == Some(0) {
+                bail!("exchange.{}.depth must be positive", name);
+            }
+            if name != "binance" {
+                continue;
+            }
+            if !matches!(section.depth, None | Some(5 | 10 | 20)) {
+                bail!("exchange.binance.depth must be 5, 10 or 20");
+            }
+            if !matches!(section.update_speed, None | Some(100 | 1000)) {
+                bail!("exchange.binance.update_speed must be 100 or 1000");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Effective configuration as TOML
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// Same configuration trading another pair
+    pub fn with_pair(&self, pair: &str) -> Self {
+        Self {
+            pair: pair.to_lowercase(),
+            ..self.clone()
+        }
+    }
+
+    /// Same configuration publishing another number of rows
+    pub fn with_top(&self, top: usize) -> Self {
+        Self {
+            top,
+            ..self.clone()
+        }
+    }
+
+    pub fn config(&self) -> Option<&Path> {
+        self.config.as_deref()
+    }
+
+    pub fn watch_config(&self) -> bool {
+        self.watch_config
+    }
+
+    pub fn check_config(&self) -> bool {
+        self.check_config
+    }
+
     pub fn pair(&self) -> &str {
         self.pair.as_str()
     }
 
-    #[cfg(feature = "binance")]
+    pub fn exchanges(&self) -> &[String] {
+        self.exchanges.as_slice()
+    }
+
+    pub fn exchange(&self, name: &str) -> Option<&Exchange> {
+        self.exchange.get(name.to_lowercase().as_str())
+    }
+
+    /// Levels to keep from an exchange, `None` keeps what the exchange sends
+    pub fn depth(&self, name: &str) -> Option<usize> {
+        self.exchange(name).and_then(|section| section.depth)
+    }
+
+    pub fn update_speed(&self, name: &str) -> Option<u64> {
+        self.exchange(name).and_then(|section| section.update_speed)
+    }
+
+    /// URL setting of a built-in exchange
+    pub fn url(&self, name: &str) -> Option<&url::Url> {
+        match name.to_lowercase().as_str() {
+            "binance" => Some(&self.binance_url),
+            "bitstamp" => Some(&self.bitstamp_url),
+            "gemini" => Some(&self.gemini_url),
+            "htx" => Some(&self.htx_url),
+            "kucoin" => Some(&self.kucoin_url),
+            _ => None,
+        }
+    }
+
     pub const fn binance_url(&self) -> &url::Url {
         &self.binance_url
     }
 
-    #[cfg(feature = "bitstamp")]
     pub const fn bitstamp_url(&self) -> &url::Url {
         &self.bitstamp_url
     }
 
+    pub const fn gemini_url(&self) -> &url::Url {
+        &self.gemini_url
+    }
+
+    pub fn generic_definitions(&self) -> &[PathBuf] {
+        self.generic_definition.as_slice()
+    }
+
+    pub const fn htx_url(&self) -> &url::Url {
+        &self.htx_url
+    }
+
+    pub const fn kucoin_url(&self) -> &url::Url {
+        &self.kucoin_url
+    }
+
     pub fn top(&self) -> usize {
         self.top
     }
@@ -70,7 +708,404 @@ impl Config {
         self.local_bind
     }
 
+    pub fn summary_timestamps(&self) -> bool {
+        self.summary_timestamps
+    }
+
+    pub fn metrics_bind(&self) -> SocketAddr {
+        self.metrics_bind
+    }
+
+    /// Whether the frames of the exchange are captured, the section overrides `record`
+    pub fn record(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+
+        match self.exchange(&name).and_then(|section| section.record) {
+            Some(record) => record,
+            None => self
+                .record
+                .iter()
+                .any(|exchange| exchange.to_lowercase() == name),
+        }
+    }
+
+    pub fn record_dir(&self) -> &Path {
+        self.record_dir.as_path()
+    }
+
+    pub fn record_max_bytes(&self) -> u64 {
+        self.record_max_bytes
+    }
+
+    pub fn record_max_age(&self) -> Duration {
+        Duration::from_secs(self.record_max_age)
+    }
+
+    pub fn replay(&self) -> &[PathBuf] {
+        self.replay.as_slice()
+    }
+
+    pub fn replay_speed(&self) -> Speed {
+        self.replay_speed
+    }
+
+    /// Validation policy of the exchange, the section overrides `validation`
+    pub fn validation(&self, name: &str) -> Policy {
+        self.exchange(name)
+            .and_then(|section| section.validation)
+            .unwrap_or(self.validation)
+    }
+
+    pub fn quarantine(&self) -> Duration {
+        Duration::from_secs(self.quarantine)
+    }
+
+    pub fn fee_adjusted(&self) -> bool {
+        self.fee_adjusted
+    }
+
+    pub fn fees(&self, name: &str) -> Fees {
+        self.exchange(name)
+            .and_then(|section| section.fees.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn pricing(&self, estimator: Estimator) -> bool {
+        self.pricing.contains(&estimator)
+    }
+
+    pub fn pricing_band(&self) -> f64 {
+        self.pricing_band
+    }
+
+    /// Share of the exchange in the exchange-weighted mid
+    pub fn weight(&self, name: &str) -> f64 {
+        self.exchange(name)
+            .and_then(|section| section.weight)
+            .unwrap_or(1.0)
+    }
+
+    pub fn arbitrage_edge(&self) -> f64 {
+        self.arbitrage_edge
+    }
+
+    pub fn arbitrage_history(&self) -> usize {
+        self.arbitrage_history
+    }
+
+    pub fn breaker_threshold(&self) -> f64 {
+        self.breaker_threshold
+    }
+
+    pub fn breaker_window(&self) -> Duration {
+        Duration::from_secs(self.breaker_window)
+    }
+
+    /// Whether the feed of the exchange gets faults injected, the section overrides `chaos`
+    pub fn chaos(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+
+        match self.exchange(&name).and_then(|section| section.chaos) {
+            Some(chaos) => chaos,
+            None => self
+                .chaos
+                .iter()
+                .any(|exchange| exchange.to_lowercase() == name),
+        }
+    }
+
+    pub fn faults(&self) -> Faults {
+        Faults {
+            seed: self.chaos_seed,
+            latency: Duration::from_millis(self.chaos_latency),
+            drop: self.chaos_drop,
+            duplicate: self.chaos_duplicate,
+            reorder: self.chaos_reorder,
+            truncate: self.chaos_truncate,
+            disconnect: self.chaos_disconnect,
+        }
+    }
+
+    pub fn simulation(&self) -> Simulation {
+        Simulation {
+            seed: self.simulator_seed,
+            mid: self.simulator_mid,
+            spread: self.simulator_spread,
+            tick: self.simulator_tick,
+            volatility: self.simulator_volatility,
+            amount: self.simulator_amount,
+            growth: self.simulator_growth,
+            interval: Duration::from_millis(self.simulator_interval),
+            shock: self.simulator_shock,
+            cross: self.simulator_cross,
+        }
+    }
+
+    pub fn log_format(&self) -> LogFormat {
+        self.log_format
+    }
+
+    pub fn otlp_endpoint(&self) -> Option<&url::Url> {
+        self.otlp_endpoint.as_ref()
+    }
+
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout)
+    }
+
     pub fn cli(&self) -> bool {
         self.cli
     }
 }
+
+fn display<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: fmt::Display,
+    S: serde::Serializer,
+{
+    serializer.collect_str(value)
+}
+
+/// TOML unless the extension says YAML
+fn read(path: &Path) -> Result<Map<String, Value>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let value: Value = match path.extension().and_then(|e| e.to_str()) {
+        Some("yaml") | Some("yml") => serde_yaml::from_str(content.as_str())?,
+        _ => toml::from_str(content.as_str())?,
+    };
+
+    match value {
+        Value::Object(map) => Ok(map),
+        _ => Err(anyhow!("{} is not a table", path.display())),
+    }
+}
+
+fn defaulted(matches: &ArgMatches, id: &str) -> bool {
+    matches!(
+        matches.value_source(id),
+        None | Some(ValueSource::DefaultValue)
+    )
+}
+
+fn file_args(file: &Map<String, Value>, matches: &ArgMatches) -> Result<Vec<OsString>> {
+    let mut values: Vec<(String, &Value)> = file
+        .iter()
+        .filter(|(key, _)| key.as_str() != "exchange")
+        .map(|(key, value)| (key.replace('-', "_"), value))
+        .collect();
+
+    // section URLs stand for the `<name>_url` arguments
+    if let Some(Value::Object(sections)) = file.get("exchange") {
+        for (name, section) in sections {
+            if let Some(url) = section.get("url") {
+                values.push((format!("{}_url", name.to_lowercase()), url));
+            }
+        }
+    }
+
+    let command = Config::command();
+    let mut args = Vec::<OsString>::new();
+
+    for (id, value) in values {
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_id().as_str() == id)
+            .ok_or_else(|| anyhow!("unknown configuration key {}", id))?;
+
+        if id == "config" || !defaulted(matches, id.as_str()) {
+            continue;
+        }
+
+        let flag = format!("--{}", arg.get_long().unwrap_or(id.as_str()));
+
+        match value {
+            Value::Bool(set) if !arg.get_action().takes_values() => {
+                if *set {
+                    args.push(flag.into());
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    args.push(flag.as_str().into());
+                    args.push(scalar(item)?.into());
+                }
+            }
+            other => {
+                args.push(flag.into());
+                args.push(scalar(other)?.into());
+            }
+        }
+    }
+
+    Ok(args)
+}
+
+fn scalar(value: &Value) -> Result<String> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(flag) => Ok(flag.to_string()),
+        other => Err(anyhow!("unexpected configuration value {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn file(name: &str, content: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("algo-{}-{}", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_defaults() -> Result<()> {
+        let config = Config::load_from(["algo"])?;
+
+        assert_eq!(config.pair(), "ethbtc");
+        assert_eq!(config.exchanges(), ["binance", "bitstamp"]);
+        assert_eq!(config.top(), 10);
+        assert!(config.pricing(Estimator::Exchange));
+        assert!(config.validate().is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_below_command_line() -> Result<()> {
+        let path = file(
+            "layers.toml",
+            r#"
+            pair = "btcusdt"
+            top = 5
+            cli = true
+            exchanges = ["binance", "bitstamp", "htx"]
+            validation = "drop-frame"
+            pricing = ["mid", "depth"]
+
+            [exchange.binance]
+            url = "ws://127.0.0.1:9443/ws/"
+            depth = 5
+            update_speed = 1000
+            weight = 2.5
+
+            [exchange.binance.fees]
+            maker = 10
+            taker = 10
+            volume = 2000000
+            tiers = [
+                { volume = 1000000, maker = 9, taker = 8 },
+                { volume = 5000000, maker = 8, taker = 6 },
+            ]
+
+            [exchange.bitstamp]
+            enabled = false
+            validation = "quarantine"
+            "#,
+        );
+
+        let config = Config::load_from([
+            OsString::from("algo"),
+            OsString::from("--config"),
+            path.clone().into_os_string(),
+            OsString::from("--top"),
+            OsString::from("7"),
+        ])?;
+
+        assert_eq!(config.pair(), "btcusdt");
+        assert_eq!(config.top(), 7);
+        assert!(config.cli());
+        assert_eq!(config.exchanges(), ["binance", "htx"]);
+        assert_eq!(config.binance_url().as_str(), "ws://127.0.0.1:9443/ws/");
+        assert_eq!(config.depth("Binance"), Some(5));
+        assert_eq!(config.update_speed("binance"), Some(1000));
+        assert_eq!(config.depth("htx"), None);
+        assert_eq!(config.validation("binance"), Policy::DropFrame);
+        assert_eq!(config.validation("bitstamp"), Policy::Quarantine);
+        assert_eq!(config.fees("Binance").maker(), 9.0);
+        assert_eq!(config.fees("binance").taker(), 8.0);
+        assert_eq!(config.fees("bitstamp").taker(), 0.0);
+        assert!(config.pricing(Estimator::Depth));
+        assert!(!config.pricing(Estimator::Micro));
+        assert_eq!(config.weight("Binance"), 2.5);
+        assert_eq!(config.weight("htx"), 1.0);
+        assert!(config.validate().is_ok());
+
+        let printed = config.to_toml()?;
+        assert!(printed.contains("pair = \"btcusdt\""));
+        assert!(printed.contains("[exchange.binance]"));
+
+        fs::remove_file(path)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_yaml_and_validation() -> Result<()> {
+        let path = file(
+            "invalid.yaml",
+            "pair: ethbtc\nexchange:\n  binance:\n    update_speed: 250\n",
+        );
+
+        let config = Config::load_from([
+            OsString::from("algo"),
+            OsString::from("--config"),
+            path.clone().into_os_string(),
+        ])?;
+
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "exchange.binance.update_speed must be 100 or 1000"
+        );
+
+        fs::remove_file(path)?;
+
+        let config = Config::load_toml("[exchange.binance]\ndepth = 15\n", ["algo"])?;
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "exchange.binance.depth must be 5, 10 or 20"
+        );
+        let config = Config::load_toml(
+            "[exchange.binance]\ndepth = 10\n\n[exchange.htx]\nupdate_speed = 250\n",
+            ["algo"],
+        )?;
+        assert!(config.validate().is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_key() {
+        let path = file("unknown.toml", "colour = \"blue\"\n");
+
+        let error = Config::load_from([
+            OsString::from("algo"),
+            OsString::from("--config"),
+            path.clone().into_os_string(),
+        ])
+        .err()
+        .unwrap();
+
+        assert_eq!(error.to_string(), "unknown configuration key colour");
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_toml_text() -> Result<()> {
+        let config = Config::load_toml(
+            "top = 5\n\n[exchange.htx]\nenabled = true\n",
+            ["algo", "--top", "3"],
+        )?;
+
+        assert_eq!(config.top(), 3);
+        assert_eq!(config.exchanges(), ["binance", "bitstamp", "htx"]);
+        assert!(Config::load_toml("colour = \"blue\"\n", ["algo"]).is_err());
+
+        Ok(())
+    }
+}
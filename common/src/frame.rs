@@ -0,0 +1,85 @@
+use anyhow::{anyhow, Result};
+use flate2::read::GzDecoder;
+use std::io::Read;
+use tungstenite::Message;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Payload of a websocket message once the transport details are stripped
+pub enum Frame {
+    Text(String),
+    Control,
+}
+
+impl Frame {
+    /// Text and binary messages become text, gzip binary payloads are inflated
+    pub fn decode(message: Message) -> Result<Self> {
+        match message {
+            Message::Text(text) => Ok(Frame::Text(text)),
+            Message::Binary(data) => Ok(Frame::Text(inflate(&data)?)),
+            Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => Ok(Frame::Control),
+            Message::Close(frame) => Err(anyhow!("closed by peer {:?}", frame)),
+        }
+    }
+}
+
+/// Reads until a data frame arrives, control frames are answered by tungstenite itself
+pub fn read_text<F>(mut read: F) -> Result<String>
+where
+    F: FnMut() -> Result<Message>,
+{
+    loop {
+        if let Frame::Text(text) = Frame::decode(read()?)? {
+            return Ok(text);
+        }
+    }
+}
+
+/// Binary payload as text, gzip payloads are inflated first
+pub fn inflate(data: &[u8]) -> Result<String> {
+    if data.starts_with(&GZIP_MAGIC) {
+        let mut text = String::new();
+        GzDecoder::new(data).read_to_string(&mut text)?;
+        Ok(text)
+    } else {
+        Ok(String::from_utf8(data.to_vec())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    fn gzip(text: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_read_text() -> Result<()> {
+        let mut messages = vec![
+            Message::Binary(gzip("{\"ping\":1}")),
+            Message::Ping(vec![]),
+            Message::Binary(b"plain".to_vec()),
+            Message::Text(String::from("text")),
+        ]
+        .into_iter();
+
+        let mut next = || messages.next().ok_or_else(|| anyhow!("eof"));
+
+        assert_eq!(read_text(&mut next)?, "{\"ping\":1}");
+        assert_eq!(read_text(&mut next)?, "plain");
+        assert_eq!(read_text(&mut next)?, "text");
+        assert!(read_text(&mut next).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_close() {
+        assert!(Frame::decode(Message::Close(None)).is_err());
+    }
+}
@@ -1,6 +1,15 @@
+pub mod book;
+pub mod chaos;
+pub mod clock;
 pub mod config;
+pub mod frame;
 pub mod orderbook;
+pub mod pair;
 pub mod provider;
+pub mod recorder;
+pub mod registry;
+pub mod transport;
 
 pub use config::ConfigRef;
 pub use provider::Provider;
+pub use transport::Transport;
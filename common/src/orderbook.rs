@@ -1,6 +1,65 @@
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Empty {}
+/// buys walk the merged asks and sells the merged bids, all levels and not only the top
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QuoteRequest {
+    #[prost(enumeration = "Side", tag = "1")]
+    pub side: i32,
+    #[prost(oneof = "quote_request::Quantity", tags = "2, 3")]
+    pub quantity: ::core::option::Option<quote_request::Quantity>,
+}
+/// Nested message and enum types in `QuoteRequest`.
+pub mod quote_request {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Quantity {
+        /// amount of the base currency to fill
+        #[prost(double, tag = "2")]
+        Base(f64),
+        /// amount of the quote currency to spend or receive
+        #[prost(double, tag = "3")]
+        Quote(f64),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Cost {
+    #[prost(double, tag = "1")]
+    pub vwap: f64,
+    /// highest price bought at or lowest price sold at
+    #[prost(double, tag = "2")]
+    pub worst_price: f64,
+    #[prost(double, tag = "3")]
+    pub mid: f64,
+    /// vwap away from mid in basis points, positive when worse
+    #[prost(double, tag = "4")]
+    pub slippage: f64,
+    /// what got filled, less than asked when insufficient
+    #[prost(double, tag = "5")]
+    pub base: f64,
+    #[prost(double, tag = "6")]
+    pub quote: f64,
+    #[prost(message, repeated, tag = "7")]
+    pub fills: ::prost::alloc::vec::Vec<Fill>,
+    #[prost(bool, tag = "8")]
+    pub insufficient: bool,
+    /// vwap after the taker fees, zero unless --fee-adjusted
+    #[prost(double, tag = "9")]
+    pub effective_vwap: f64,
+}
+/// share of the fill taken from one exchange
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Fill {
+    #[prost(string, tag = "1")]
+    pub exchange: ::prost::alloc::string::String,
+    #[prost(double, tag = "2")]
+    pub base: f64,
+    #[prost(double, tag = "3")]
+    pub quote: f64,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Summary {
@@ -10,6 +69,68 @@ pub struct Summary {
     pub bids: ::prost::alloc::vec::Vec<Level>,
     #[prost(message, repeated, tag = "3")]
     pub asks: ::prost::alloc::vec::Vec<Level>,
+    /// unix microseconds, zero when unknown or not published (see --summary-timestamps)
+    /// exchange event and socket receipt are the oldest of the merged books
+    #[prost(uint64, tag = "4")]
+    pub event_timestamp: u64,
+    #[prost(uint64, tag = "5")]
+    pub receive_timestamp: u64,
+    #[prost(uint64, tag = "6")]
+    pub merge_timestamp: u64,
+    #[prost(uint64, tag = "7")]
+    pub send_timestamp: u64,
+    /// exchanges left out by the outlier circuit breaker, quoting away from the others
+    #[prost(string, repeated, tag = "8")]
+    pub excluded: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// fair mid-price estimators of each merge, zero when left out of --pricing or a side is empty
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Prices {
+    /// halfway between the best bid and ask
+    #[prost(double, tag = "1")]
+    pub mid: f64,
+    /// best bid and ask weighted by the amount across, leaning towards the thinner side
+    #[prost(double, tag = "2")]
+    pub micro_price: f64,
+    /// bid and ask vwaps within --pricing-band of mid, weighted by the depth across
+    #[prost(double, tag = "3")]
+    pub depth_mid: f64,
+    /// mids of the exchanges weighted by their \[exchange.<name>\] weight
+    #[prost(double, tag = "4")]
+    pub exchange_mid: f64,
+}
+/// window during which buying the best ask of one exchange and selling into the best bid of
+/// another pays after both taker fees, the stream opens with the last ended and the open windows
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Arbitrage {
+    #[prost(enumeration = "Phase", tag = "1")]
+    pub phase: i32,
+    /// exchange whose ask is bought
+    #[prost(string, tag = "2")]
+    pub buy: ::prost::alloc::string::String,
+    /// exchange whose bid is sold into
+    #[prost(string, tag = "3")]
+    pub sell: ::prost::alloc::string::String,
+    #[prost(double, tag = "4")]
+    pub ask: f64,
+    #[prost(double, tag = "5")]
+    pub bid: f64,
+    /// executable on both tops at once
+    #[prost(double, tag = "6")]
+    pub amount: f64,
+    /// net of taker fees for the whole amount, in the quote currency
+    #[prost(double, tag = "7")]
+    pub profit: f64,
+    /// net of taker fees in basis points of the cost
+    #[prost(double, tag = "8")]
+    pub edge: f64,
+    /// unix microseconds the window opened, and how long it has lasted
+    #[prost(uint64, tag = "9")]
+    pub start_timestamp: u64,
+    #[prost(uint64, tag = "10")]
+    pub duration: u64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -20,6 +141,102 @@ pub struct Level {
     pub price: f64,
     #[prost(double, tag = "3")]
     pub amount: f64,
+    /// price after the taker fee, less for bids and more for asks, zero unless --fee-adjusted
+    #[prost(double, tag = "4")]
+    pub effective_price: f64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProviderRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProviderState {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub connected: bool,
+    #[prost(bool, tag = "3")]
+    pub subscribed: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProviderList {
+    #[prost(string, tag = "1")]
+    pub pair: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub depth: u32,
+    #[prost(message, repeated, tag = "3")]
+    pub providers: ::prost::alloc::vec::Vec<ProviderState>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PairRequest {
+    #[prost(string, tag = "1")]
+    pub pair: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DepthRequest {
+    #[prost(uint32, tag = "1")]
+    pub depth: u32,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum Side {
+    Buy = 0,
+    Sell = 1,
+}
+impl Side {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Side::Buy => "BUY",
+            Side::Sell => "SELL",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "BUY" => Some(Self::Buy),
+            "SELL" => Some(Self::Sell),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum Phase {
+    Start = 0,
+    Update = 1,
+    End = 2,
+}
+impl Phase {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Phase::Start => "START",
+            Phase::Update => "UPDATE",
+            Phase::End => "END",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "START" => Some(Self::Start),
+            "UPDATE" => Some(Self::Update),
+            "END" => Some(Self::End),
+            _ => None,
+        }
+    }
 }
 /// Generated client implementations.
 pub mod orderbook_aggregator_client {
@@ -128,62 +345,176 @@ pub mod orderbook_aggregator_client {
                 .insert(GrpcMethod::new("orderbook.OrderbookAggregator", "BookSummary"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn book_summary_stream(
+            &mut self,
+            request: impl tonic::IntoRequest<super::Empty>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::Summary>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/orderbook.OrderbookAggregator/BookSummaryStream",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("orderbook.OrderbookAggregator", "BookSummaryStream"),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn quote(
+            &mut self,
+            request: impl tonic::IntoRequest<super::QuoteRequest>,
+        ) -> std::result::Result<tonic::Response<super::Cost>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/orderbook.OrderbookAggregator/Quote",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("orderbook.OrderbookAggregator", "Quote"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn price_stream(
+            &mut self,
+            request: impl tonic::IntoRequest<super::Empty>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::Prices>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/orderbook.OrderbookAggregator/PriceStream",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("orderbook.OrderbookAggregator", "PriceStream"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn arbitrage_stream(
+            &mut self,
+            request: impl tonic::IntoRequest<super::Empty>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::Arbitrage>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/orderbook.OrderbookAggregator/ArbitrageStream",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("orderbook.OrderbookAggregator", "ArbitrageStream"),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
     }
 }
-/// Generated server implementations.
-pub mod orderbook_aggregator_server {
+/// Generated client implementations.
+pub mod admin_client {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
     use tonic::codegen::*;
-    /// Generated trait containing gRPC methods that should be implemented for use with OrderbookAggregatorServer.
-    #[async_trait]
-    pub trait OrderbookAggregator: Send + Sync + 'static {
-        async fn book_summary(
-            &self,
-            request: tonic::Request<super::Empty>,
-        ) -> std::result::Result<tonic::Response<super::Summary>, tonic::Status>;
+    use tonic::codegen::http::Uri;
+    #[derive(Debug, Clone)]
+    pub struct AdminClient<T> {
+        inner: tonic::client::Grpc<T>,
     }
-    #[derive(Debug)]
-    pub struct OrderbookAggregatorServer<T: OrderbookAggregator> {
-        inner: _Inner<T>,
-        accept_compression_encodings: EnabledCompressionEncodings,
-        send_compression_encodings: EnabledCompressionEncodings,
-        max_decoding_message_size: Option<usize>,
-        max_encoding_message_size: Option<usize>,
+    impl AdminClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
     }
-    struct _Inner<T>(Arc<T>);
-    impl<T: OrderbookAggregator> OrderbookAggregatorServer<T> {
+    impl<T> AdminClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
         pub fn new(inner: T) -> Self {
-            Self::from_arc(Arc::new(inner))
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
         }
-        pub fn from_arc(inner: Arc<T>) -> Self {
-            let inner = _Inner(inner);
-            Self {
-                inner,
-                accept_compression_encodings: Default::default(),
-                send_compression_encodings: Default::default(),
-                max_decoding_message_size: None,
-                max_encoding_message_size: None,
-            }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
         }
         pub fn with_interceptor<F>(
             inner: T,
             interceptor: F,
-        ) -> InterceptedService<Self, F>
+        ) -> AdminClient<InterceptedService<T, F>>
         where
             F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + Send + Sync,
         {
-            InterceptedService::new(Self::new(inner), interceptor)
+            AdminClient::new(InterceptedService::new(inner, interceptor))
         }
-        /// Enable decompressing requests with the given encoding.
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
         #[must_use]
-        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.accept_compression_encodings.enable(encoding);
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
             self
         }
-        /// Compress responses with the given encoding, if the client supports it.
+        /// Enable decompressing responses.
         #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.send_compression_encodings.enable(encoding);
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
             self
         }
         /// Limits the maximum size of a decoded message.
@@ -191,7 +522,7 @@ pub mod orderbook_aggregator_server {
         /// Default: `4MB`
         #[must_use]
         pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
-            self.max_decoding_message_size = Some(limit);
+            self.inner = self.inner.max_decoding_message_size(limit);
             self
         }
         /// Limits the maximum size of an encoded message.
@@ -199,46 +530,668 @@ pub mod orderbook_aggregator_server {
         /// Default: `usize::MAX`
         #[must_use]
         pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
-            self.max_encoding_message_size = Some(limit);
+            self.inner = self.inner.max_encoding_message_size(limit);
             self
         }
-    }
-    impl<T, B> tonic::codegen::Service<http::Request<B>> for OrderbookAggregatorServer<T>
-    where
-        T: OrderbookAggregator,
-        B: Body + Send + 'static,
-        B::Error: Into<StdError> + Send + 'static,
-    {
-        type Response = http::Response<tonic::body::BoxBody>;
-        type Error = std::convert::Infallible;
-        type Future = BoxFuture<Self::Response, Self::Error>;
-        fn poll_ready(
+        pub async fn list_providers(
             &mut self,
-            _cx: &mut Context<'_>,
-        ) -> Poll<std::result::Result<(), Self::Error>> {
-            Poll::Ready(Ok(()))
+            request: impl tonic::IntoRequest<super::Empty>,
+        ) -> std::result::Result<tonic::Response<super::ProviderList>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/orderbook.Admin/ListProviders",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("orderbook.Admin", "ListProviders"));
+            self.inner.unary(req, path, codec).await
         }
-        fn call(&mut self, req: http::Request<B>) -> Self::Future {
-            let inner = self.inner.clone();
-            match req.uri().path() {
-                "/orderbook.OrderbookAggregator/BookSummary" => {
-                    #[allow(non_camel_case_types)]
-                    struct BookSummarySvc<T: OrderbookAggregator>(pub Arc<T>);
-                    impl<
-                        T: OrderbookAggregator,
-                    > tonic::server::UnaryService<super::Empty> for BookSummarySvc<T> {
-                        type Response = super::Summary;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
+        pub async fn subscribe(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ProviderRequest>,
+        ) -> std::result::Result<tonic::Response<super::ProviderState>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/orderbook.Admin/Subscribe",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("orderbook.Admin", "Subscribe"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn unsubscribe(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ProviderRequest>,
+        ) -> std::result::Result<tonic::Response<super::ProviderState>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/orderbook.Admin/Unsubscribe",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("orderbook.Admin", "Unsubscribe"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn reconnect(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ProviderRequest>,
+        ) -> std::result::Result<tonic::Response<super::ProviderState>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/orderbook.Admin/Reconnect",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("orderbook.Admin", "Reconnect"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn set_pair(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PairRequest>,
+        ) -> std::result::Result<tonic::Response<super::ProviderList>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/orderbook.Admin/SetPair");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("orderbook.Admin", "SetPair"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn set_depth(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DepthRequest>,
+        ) -> std::result::Result<tonic::Response<super::ProviderList>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/orderbook.Admin/SetDepth");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("orderbook.Admin", "SetDepth"));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod orderbook_aggregator_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with OrderbookAggregatorServer.
+    #[async_trait]
+    pub trait OrderbookAggregator: Send + Sync + 'static {
+        async fn book_summary(
+            &self,
+            request: tonic::Request<super::Empty>,
+        ) -> std::result::Result<tonic::Response<super::Summary>, tonic::Status>;
+        /// Server streaming response type for the BookSummaryStream method.
+        type BookSummaryStreamStream: futures_core::Stream<
+                Item = std::result::Result<super::Summary, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn book_summary_stream(
+            &self,
+            request: tonic::Request<super::Empty>,
+        ) -> std::result::Result<
+            tonic::Response<Self::BookSummaryStreamStream>,
+            tonic::Status,
+        >;
+        async fn quote(
+            &self,
+            request: tonic::Request<super::QuoteRequest>,
+        ) -> std::result::Result<tonic::Response<super::Cost>, tonic::Status>;
+        /// Server streaming response type for the PriceStream method.
+        type PriceStreamStream: futures_core::Stream<
+                Item = std::result::Result<super::Prices, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn price_stream(
+            &self,
+            request: tonic::Request<super::Empty>,
+        ) -> std::result::Result<
+            tonic::Response<Self::PriceStreamStream>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the ArbitrageStream method.
+        type ArbitrageStreamStream: futures_core::Stream<
+                Item = std::result::Result<super::Arbitrage, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn arbitrage_stream(
+            &self,
+            request: tonic::Request<super::Empty>,
+        ) -> std::result::Result<
+            tonic::Response<Self::ArbitrageStreamStream>,
+            tonic::Status,
+        >;
+    }
+    #[derive(Debug)]
+    pub struct OrderbookAggregatorServer<T: OrderbookAggregator> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: OrderbookAggregator> OrderbookAggregatorServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for OrderbookAggregatorServer<T>
+    where
+        T: OrderbookAggregator,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/orderbook.OrderbookAggregator/BookSummary" => {
+                    #[allow(non_camel_case_types)]
+                    struct BookSummarySvc<T: OrderbookAggregator>(pub Arc<T>);
+                    impl<
+                        T: OrderbookAggregator,
+                    > tonic::server::UnaryService<super::Empty> for BookSummarySvc<T> {
+                        type Response = super::Summary;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::Empty>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).book_summary(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = BookSummarySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/orderbook.OrderbookAggregator/BookSummaryStream" => {
+                    #[allow(non_camel_case_types)]
+                    struct BookSummaryStreamSvc<T: OrderbookAggregator>(pub Arc<T>);
+                    impl<
+                        T: OrderbookAggregator,
+                    > tonic::server::ServerStreamingService<super::Empty>
+                    for BookSummaryStreamSvc<T> {
+                        type Response = super::Summary;
+                        type ResponseStream = T::BookSummaryStreamStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::Empty>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).book_summary_stream(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = BookSummaryStreamSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/orderbook.OrderbookAggregator/Quote" => {
+                    #[allow(non_camel_case_types)]
+                    struct QuoteSvc<T: OrderbookAggregator>(pub Arc<T>);
+                    impl<
+                        T: OrderbookAggregator,
+                    > tonic::server::UnaryService<super::QuoteRequest> for QuoteSvc<T> {
+                        type Response = super::Cost;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::QuoteRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).quote(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = QuoteSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/orderbook.OrderbookAggregator/PriceStream" => {
+                    #[allow(non_camel_case_types)]
+                    struct PriceStreamSvc<T: OrderbookAggregator>(pub Arc<T>);
+                    impl<
+                        T: OrderbookAggregator,
+                    > tonic::server::ServerStreamingService<super::Empty>
+                    for PriceStreamSvc<T> {
+                        type Response = super::Prices;
+                        type ResponseStream = T::PriceStreamStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::Empty>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).price_stream(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PriceStreamSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/orderbook.OrderbookAggregator/ArbitrageStream" => {
+                    #[allow(non_camel_case_types)]
+                    struct ArbitrageStreamSvc<T: OrderbookAggregator>(pub Arc<T>);
+                    impl<
+                        T: OrderbookAggregator,
+                    > tonic::server::ServerStreamingService<super::Empty>
+                    for ArbitrageStreamSvc<T> {
+                        type Response = super::Arbitrage;
+                        type ResponseStream = T::ArbitrageStreamStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::Empty>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).arbitrage_stream(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ArbitrageStreamSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: OrderbookAggregator> Clone for OrderbookAggregatorServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    impl<T: OrderbookAggregator> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(Arc::clone(&self.0))
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: OrderbookAggregator> tonic::server::NamedService
+    for OrderbookAggregatorServer<T> {
+        const NAME: &'static str = "orderbook.OrderbookAggregator";
+    }
+}
+/// Generated server implementations.
+pub mod admin_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with AdminServer.
+    #[async_trait]
+    pub trait Admin: Send + Sync + 'static {
+        async fn list_providers(
+            &self,
+            request: tonic::Request<super::Empty>,
+        ) -> std::result::Result<tonic::Response<super::ProviderList>, tonic::Status>;
+        async fn subscribe(
+            &self,
+            request: tonic::Request<super::ProviderRequest>,
+        ) -> std::result::Result<tonic::Response<super::ProviderState>, tonic::Status>;
+        async fn unsubscribe(
+            &self,
+            request: tonic::Request<super::ProviderRequest>,
+        ) -> std::result::Result<tonic::Response<super::ProviderState>, tonic::Status>;
+        async fn reconnect(
+            &self,
+            request: tonic::Request<super::ProviderRequest>,
+        ) -> std::result::Result<tonic::Response<super::ProviderState>, tonic::Status>;
+        async fn set_pair(
+            &self,
+            request: tonic::Request<super::PairRequest>,
+        ) -> std::result::Result<tonic::Response<super::ProviderList>, tonic::Status>;
+        async fn set_depth(
+            &self,
+            request: tonic::Request<super::DepthRequest>,
+        ) -> std::result::Result<tonic::Response<super::ProviderList>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct AdminServer<T: Admin> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: Admin> AdminServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for AdminServer<T>
+    where
+        T: Admin,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/orderbook.Admin/ListProviders" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListProvidersSvc<T: Admin>(pub Arc<T>);
+                    impl<T: Admin> tonic::server::UnaryService<super::Empty>
+                    for ListProvidersSvc<T> {
+                        type Response = super::ProviderList;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
                             &mut self,
                             request: tonic::Request<super::Empty>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                (*inner).book_summary(request).await
+                                (*inner).list_providers(request).await
                             };
                             Box::pin(fut)
                         }
@@ -250,7 +1203,217 @@ pub mod orderbook_aggregator_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = BookSummarySvc(inner);
+                        let method = ListProvidersSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/orderbook.Admin/Subscribe" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubscribeSvc<T: Admin>(pub Arc<T>);
+                    impl<T: Admin> tonic::server::UnaryService<super::ProviderRequest>
+                    for SubscribeSvc<T> {
+                        type Response = super::ProviderState;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ProviderRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).subscribe(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SubscribeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/orderbook.Admin/Unsubscribe" => {
+                    #[allow(non_camel_case_types)]
+                    struct UnsubscribeSvc<T: Admin>(pub Arc<T>);
+                    impl<T: Admin> tonic::server::UnaryService<super::ProviderRequest>
+                    for UnsubscribeSvc<T> {
+                        type Response = super::ProviderState;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ProviderRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).unsubscribe(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UnsubscribeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/orderbook.Admin/Reconnect" => {
+                    #[allow(non_camel_case_types)]
+                    struct ReconnectSvc<T: Admin>(pub Arc<T>);
+                    impl<T: Admin> tonic::server::UnaryService<super::ProviderRequest>
+                    for ReconnectSvc<T> {
+                        type Response = super::ProviderState;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ProviderRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).reconnect(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ReconnectSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/orderbook.Admin/SetPair" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetPairSvc<T: Admin>(pub Arc<T>);
+                    impl<T: Admin> tonic::server::UnaryService<super::PairRequest>
+                    for SetPairSvc<T> {
+                        type Response = super::ProviderList;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PairRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).set_pair(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SetPairSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/orderbook.Admin/SetDepth" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetDepthSvc<T: Admin>(pub Arc<T>);
+                    impl<T: Admin> tonic::server::UnaryService<super::DepthRequest>
+                    for SetDepthSvc<T> {
+                        type Response = super::ProviderList;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DepthRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).set_depth(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SetDepthSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -281,7 +1444,7 @@ pub mod orderbook_aggregator_server {
             }
         }
     }
-    impl<T: OrderbookAggregator> Clone for OrderbookAggregatorServer<T> {
+    impl<T: Admin> Clone for AdminServer<T> {
         fn clone(&self) -> Self {
             let inner = self.inner.clone();
             Self {
@@ -293,7 +1456,7 @@ pub mod orderbook_aggregator_server {
             }
         }
     }
-    impl<T: OrderbookAggregator> Clone for _Inner<T> {
+    impl<T: Admin> Clone for _Inner<T> {
         fn clone(&self) -> Self {
             Self(Arc::clone(&self.0))
         }
@@ -303,8 +1466,7 @@ pub mod orderbook_aggregator_server {
             write!(f, "{:?}", self.0)
         }
     }
-    impl<T: OrderbookAggregator> tonic::server::NamedService
-    for OrderbookAggregatorServer<T> {
-        const NAME: &'static str = "orderbook.OrderbookAggregator";
+    impl<T: Admin> tonic::server::NamedService for AdminServer<T> {
+        const NAME: &'static str = "orderbook.Admin";
     }
 }
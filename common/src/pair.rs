@@ -0,0 +1,30 @@
+/// Quote currencies recognised at the end of a pair
+const QUOTES: [&str; 8] = ["usdt", "usdc", "btc", "eth", "kcs", "usd", "eur", "dai"];
+
+/// Base and quote of a pair, lowercase, `ethbtc` is `("eth", "btc")`
+pub fn split(pair: &str) -> Option<(String, String)> {
+    let pair = pair.to_lowercase();
+
+    QUOTES
+        .iter()
+        .find(|quote| pair.len() > quote.len() && pair.ends_with(*quote))
+        .map(|quote| {
+            let (base, quote) = pair.split_at(pair.len() - quote.len());
+            (String::from(base), String::from(quote))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let split = |pair| split(pair).map(|(base, quote)| format!("{}/{}", base, quote));
+
+        assert_eq!(split("ethbtc").as_deref(), Some("eth/btc"));
+        assert_eq!(split("BTCUSDT").as_deref(), Some("btc/usdt"));
+        assert_eq!(split("btc"), None);
+        assert_eq!(split("ethgbp"), None);
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{clock, config::Config, frame};
+use anyhow::Result;
+use log::{info, warn};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use tungstenite::Message;
+
+/// One received websocket frame, a line of a capture file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    /// Unix microseconds at socket receipt
+    pub receive_timestamp: u64,
+    pub exchange: String,
+    pub connection: u64,
+    /// `text`, `binary`, `ping`, `pong` or `close`
+    pub kind: String,
+    /// Payload, binary frames inflated to text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
+/// Writes every frame a provider receives to zstd compressed JSON lines, when enabled for the exchange
+pub struct Recorder {
+    writer: Option<Mutex<Writer>>,
+}
+
+impl Recorder {
+    /// Disabled unless the configuration records `exchange`, a capture that fails to open only warns
+    ///
+    /// `connection` names the files of the capture and goes into each record.
+    pub fn open(config: &Config, exchange: &str, connection: u64) -> Self {
+        if !config.record(exchange) {
+            return Self::disabled();
+        }
+
+        match Writer::open(config, exchange, connection) {
+            Ok(writer) => Self {
+                writer: Some(Mutex::new(writer)),
+            },
+            Err(e) => {
+                warn!("{} capture disabled - {}", exchange, e);
+                Self::disabled()
+            }
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self { writer: None }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    /// Failures are logged and never reach the feed
+    pub fn record(&self, message: &Message) {
+        if let Some(writer) = &self.writer {
+            let mut writer = writer.lock();
+
+            if let Err(e) = writer.write(message) {
+                warn!("{} capture failed - {}", writer.exchange, e);
+            }
+        }
+    }
+}
+
+struct Writer {
+    dir: PathBuf,
+    exchange: String,
+    connection: u64,
+    max_bytes: u64,
+    max_age: Duration,
+    file: Option<zstd::Encoder<'static, BufWriter<File>>>,
+    written: u64,
+    opened: Instant,
+}
+
+impl Writer {
+    fn open(config: &Config, exchange: &str, connection: u64) -> Result<Self> {
+        fs::create_dir_all(config.record_dir())?;
+
+        let mut writer = Self {
+            dir: config.record_dir().to_path_buf(),
+            exchange: exchange.to_lowercase(),
+            connection,
+            max_bytes: config.record_max_bytes(),
+            max_age: config.record_max_age(),
+            file: None,
+            written: 0,
+            opened: Instant::now(),
+        };
+        writer.rotate()?;
+
+        Ok(writer)
+    }
+
+    fn write(&mut self, message: &Message) -> Result<()> {
+        let (kind, data) = match message {
+            Message::Text(text) => ("text", Some(text.clone())),
+            Message::Binary(data) => ("binary", Some(frame::inflate(data)?)),
+            Message::Ping(_) => ("ping", None),
+            Message::Pong(_) => ("pong", None),
+            Message::Close(_) => ("close", None),
+            Message::Frame(_) => return Ok(()),
+        };
+
+        let record = Record {
+            receive_timestamp: clock::micros(),
+            exchange: self.exchange.clone(),
+            connection: self.connection,
+            kind: String::from(kind),
+            data,
+        };
+
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+
+        let full = self.written + line.len() as u64 > self.max_bytes;
+
+        if self.written > 0 && (full || self.opened.elapsed() > self.max_age) {
+            self.rotate()?;
+        }
+
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(&line)?;
+            self.written += line.len() as u64;
+        }
+
+        Ok(())
+    }
+
+    /// Completes the current file and starts `<exchange>-<connection>-<micros>.jsonl.zst`
+    fn rotate(&mut self) -> Result<()> {
+        self.finish()?;
+
+        let path = self.dir.join(format!(
+            "{}-{}-{}.jsonl.zst",
+            self.exchange,
+            self.connection,
+            clock::micros()
+        ));
+        info!("{} capture to {}", self.exchange, path.display());
+
+        let file = BufWriter::new(File::create(path)?);
+        self.file = Some(zstd::Encoder::new(file, 0)?);
+        self.written = 0;
+        self.opened = Instant::now();
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if let Some(file) = self.file.take() {
+            file.finish()?.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            warn!("{} capture not completed - {}", self.exchange, e);
+        }
+    }
+}
+
+/// Records of a capture file in the order they were received
+pub fn read(path: &Path) -> Result<Vec<Record>> {
+    let text = String::from_utf8(zstd::decode_all(File::open(path)?)?)?;
+
+    text.lines()
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_rotate() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("algo-capture-{}", std::process::id()));
+
+        let config = Config::load_from([
+            "algo",
+            "--record",
+            "binance",
+            "--record-dir",
+            dir.to_str().unwrap(),
+            "--record-max-bytes",
+            "1",
+        ])?;
+
+        assert!(!Recorder::open(&config, "bitstamp", 7).is_enabled());
+
+        let recorder = Recorder::open(&config, "Binance", 7);
+        assert!(recorder.is_enabled());
+
+        let text = String::from("{\"bids\":[]}");
+        recorder.record(&Message::Text(text.clone()));
+        recorder.record(&Message::Ping(vec![]));
+        recorder.record(&Message::Text(text.clone()));
+        drop(recorder);
+
+        let mut files: Vec<PathBuf> = fs::read_dir(&dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()?;
+        files.sort();
+        // every frame past the first one overflows the file
+        assert_eq!(files.len(), 3);
+
+        let records: Vec<Record> = files
+            .iter()
+            .map(|file| read(file))
+            .collect::<Result<Vec<_>>>()?
+            .concat();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].exchange, "binance");
+        assert_eq!(records[0].connection, 7);
+        assert_eq!(records[0].data.as_deref(), Some(text.as_str()));
+        assert_eq!(records[1].kind, "ping");
+        assert!(records[1].data.is_none());
+
+        fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{ConfigRef, Provider};
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+
+pub type Factory = Box<dyn Fn(ConfigRef, u64) -> Result<Box<dyn Provider>> + Send + Sync>;
+
+/// Provider factories by exchange name, filled by the compiled-in provider crates
+#[derive(Default)]
+pub struct Registry {
+    factories: BTreeMap<String, Factory>,
+}
+
+impl Registry {
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(ConfigRef, u64) -> Result<Box<dyn Provider>> + Send + Sync + 'static,
+    {
+        self.factories
+            .insert(name.to_lowercase(), Box::new(factory));
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name.to_lowercase().as_str())
+    }
+
+    /// Provider for the numbered connection, the number tells its captures apart
+    pub fn create(
+        &self,
+        name: &str,
+        config: ConfigRef,
+        connection: u64,
+    ) -> Result<Box<dyn Provider>> {
+        match self.factories.get(name.to_lowercase().as_str()) {
+            Some(factory) => factory(config, connection),
+            None => Err(anyhow!(
+                "unknown exchange {}, available: {}",
+                name,
+                self.names().collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, orderbook::Summary};
+
+    struct Fake(&'static str);
+
+    impl Provider for Fake {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+        fn subscribe(&self) -> Result<()> {
+            Ok(())
+        }
+        fn unsubscribe(&self) -> Result<()> {
+            Ok(())
+        }
+        fn summary(&self) -> Result<Summary> {
+            Ok(Summary::default())
+        }
+    }
+
+    #[test]
+    fn test_create() -> Result<()> {
+        let mut registry = Registry::default();
+        registry.register("Alpha", |_, _| Ok(Box::new(Fake("Alpha"))));
+        registry.register("beta", |_, _| Ok(Box::new(Fake("Beta"))));
+
+        assert_eq!(registry.names().collect::<Vec<_>>(), vec!["alpha", "beta"]);
+        assert!(registry.contains("ALPHA"));
+        assert_eq!(
+            registry.create("alpha", Config::as_ref(), 1)?.name(),
+            "Alpha"
+        );
+
+        let error = registry.create("gamma", Config::as_ref(), 1).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "unknown exchange gamma, available: alpha, beta"
+        );
+
+        Ok(())
+    }
+}
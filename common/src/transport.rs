@@ -0,0 +1,235 @@
+use crate::config::Config;
+use anyhow::{anyhow, bail, Context, Result};
+use parking_lot::Mutex;
+use std::{
+    collections::VecDeque,
+    io::ErrorKind,
+    net::TcpStream,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tungstenite::{connect, stream::MaybeTlsStream, Error, Message, WebSocket};
+use url::Url;
+
+/// Websocket connection a provider talks to its exchange over
+pub trait Transport: Send + Sync {
+    /// Connects to the URL, the configuration lets a wrapper pick up its settings
+    fn connect(config: &Config, url: &Url) -> Result<Self>
+    where
+        Self: Sized;
+    fn send(&self, message: Message) -> Result<()>;
+    fn receive(&self) -> Result<Message>;
+    /// Keeps the connection alive, safe to call while another thread is blocked on `receive`
+    fn ping(&self) -> Result<()>;
+    fn close(&self);
+}
+
+/// Shared handle, lets a test keep the transport it handed to a provider
+impl<T: Transport> Transport for Arc<T> {
+    fn connect(config: &Config, url: &Url) -> Result<Self> {
+        Ok(Arc::new(T::connect(config, url)?))
+    }
+
+    fn send(&self, message: Message) -> Result<()> {
+        T::send(self, message)
+    }
+
+    fn receive(&self) -> Result<Message> {
+        T::receive(self)
+    }
+
+    fn ping(&self) -> Result<()> {
+        T::ping(self)
+    }
+
+    fn close(&self) {
+        T::close(self)
+    }
+}
+
+/// How long a read holds the socket before letting a writer in
+const READ_SLICE: Duration = Duration::from_millis(100);
+
+/// The network, TLS included for `wss://` URLs
+pub struct Tungstenite {
+    socket: Mutex<WebSocket<MaybeTlsStream<TcpStream>>>,
+}
+
+impl Transport for Tungstenite {
+    fn connect(_config: &Config, url: &Url) -> Result<Self> {
+        let (socket, _) = connect(url).with_context(|| format!("Failed to connect {}", url))?;
+
+        // reads time out in slices so the socket is free for sends between them
+        let stream = match socket.get_ref() {
+            MaybeTlsStream::Plain(stream) => Some(stream),
+            MaybeTlsStream::NativeTls(stream) => Some(stream.get_ref()),
+            _ => None,
+        };
+        if let Some(stream) = stream {
+            stream.set_read_timeout(Some(READ_SLICE))?;
+        }
+
+        Ok(Self {
+            socket: Mutex::new(socket),
+        })
+    }
+
+    fn send(&self, message: Message) -> Result<()> {
+        self.socket
+            .lock()
+            .write_message(message)
+            .with_context(|| "Failed to write message")
+    }
+
+    fn receive(&self) -> Result<Message> {
+        loop {
+            let read = self.socket.lock().read_message();
+
+            match read {
+                Err(Error::Io(e))
+                    if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                {
+                    continue
+                }
+                read => return read.with_context(|| "Failed to read message"),
+            }
+        }
+    }
+
+    fn ping(&self) -> Result<()> {
+        self.send(Message::Ping(Vec::new()))
+    }
+
+    fn close(&self) {
+        self.socket.lock().close(None).ok();
+    }
+}
+
+/// Scripted transport for tests, hands out queued messages and keeps what was sent
+#[derive(Default)]
+pub struct Memory {
+    url: Option<Url>,
+    incoming: Mutex<VecDeque<Result<Message, String>>>,
+    sent: Mutex<Vec<Message>>,
+    send_error: Mutex<Option<String>>,
+    closed: AtomicBool,
+}
+
+impl Memory {
+    /// Queues a message for `receive`
+    pub fn push(&self, message: Message) {
+        self.incoming.lock().push_back(Ok(message));
+    }
+
+    pub fn push_text(&self, text: &str) {
+        self.push(Message::Text(String::from(text)));
+    }
+
+    /// Queues a failed read
+    pub fn push_error(&self, error: &str) {
+        self.incoming.lock().push_back(Err(String::from(error)));
+    }
+
+    /// Fails every send from now on
+    pub fn fail_send(&self, error: &str) {
+        *self.send_error.lock() = Some(String::from(error));
+    }
+
+    /// Messages sent so far
+    pub fn sent(&self) -> Vec<Message> {
+        self.sent.lock().clone()
+    }
+
+    /// Queued messages not received yet
+    pub fn pending(&self) -> usize {
+        self.incoming.lock().len()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// URL it was connected to, none when built directly
+    pub fn url(&self) -> Option<&Url> {
+        self.url.as_ref()
+    }
+}
+
+impl Transport for Memory {
+    fn connect(_config: &Config, url: &Url) -> Result<Self> {
+        Ok(Self {
+            url: Some(url.clone()),
+            ..Default::default()
+        })
+    }
+
+    fn send(&self, message: Message) -> Result<()> {
+        if self.is_closed() {
+            bail!("Failed to write message - closed");
+        }
+        if let Some(error) = self.send_error.lock().as_ref() {
+            bail!("{}", error);
+        }
+        self.sent.lock().push(message);
+        Ok(())
+    }
+
+    fn ping(&self) -> Result<()> {
+        self.send(Message::Ping(Vec::new()))
+    }
+
+    fn receive(&self) -> Result<Message> {
+        if self.is_closed() {
+            bail!("Failed to read message - closed");
+        }
+        match self.incoming.lock().pop_front() {
+            Some(Ok(message)) => Ok(message),
+            Some(Err(error)) => Err(anyhow!(error)),
+            None => Err(anyhow!("Failed to read message - script finished")),
+        }
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory() -> Result<()> {
+        let memory = Memory::connect(&Config::as_ref(), &Url::parse("wss://example.com/ws")?)?;
+        assert_eq!(memory.url().map(Url::as_str), Some("wss://example.com/ws"));
+
+        memory.push_text("first");
+        memory.push_error("broken");
+        assert_eq!(memory.pending(), 2);
+
+        assert_eq!(memory.receive()?, Message::Text(String::from("first")));
+        assert_eq!(memory.receive().unwrap_err().to_string(), "broken");
+        assert!(memory.receive().is_err());
+
+        memory.send(Message::Text(String::from("request")))?;
+        memory.ping()?;
+        assert_eq!(
+            memory.sent(),
+            [
+                Message::Text(String::from("request")),
+                Message::Ping(Vec::new())
+            ]
+        );
+
+        memory.fail_send("refused");
+        assert!(memory.send(Message::Text(String::from("again"))).is_err());
+
+        memory.close();
+        assert!(memory.is_closed());
+
+        Ok(())
+    }
+}
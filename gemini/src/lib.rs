@@ -0,0 +1,19 @@
+pub(crate) mod response;
+
+pub mod provider;
+
+pub use provider::Gemini;
+
+use common::{chaos::ChaosTransport, registry::Registry, transport::Tungstenite};
+
+/// Registers the provider under `gemini`
+pub fn register(registry: &mut Registry) {
+    registry.register("gemini", |config, connection| {
+        match config.chaos("gemini") {
+            true => Ok(Box::new(Gemini::<ChaosTransport<Tungstenite>>::new(
+                config, connection,
+            )?)),
+            false => Ok(Box::new(Gemini::<Tungstenite>::new(config, connection)?)),
+        }
+    });
+}
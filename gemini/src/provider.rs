@@ -0,0 +1,229 @@
+use crate::response::Response;
+use anyhow::{anyhow, Result};
+use common::{
+    book::{Book, Side},
+    frame,
+    orderbook::Summary,
+    recorder::Recorder,
+    transport::Tungstenite,
+    ConfigRef, Provider, Transport,
+};
+use log::{debug, info};
+use parking_lot::Mutex;
+use tungstenite::Message;
+
+pub struct Gemini<T: Transport = Tungstenite> {
+    config: ConfigRef,
+    transport: T,
+    recorder: Recorder,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    book: Book,
+    seeded: bool,
+}
+
+impl<T: Transport> Drop for Gemini<T> {
+    fn drop(&mut self) {
+        info!("gemini disconnect");
+        self.transport.close();
+    }
+}
+
+impl<T: Transport> Provider for Gemini<T> {
+    fn name(&self) -> &'static str {
+        "Gemini"
+    }
+
+    fn subscribe(&self) -> Result<()> {
+        let subscribe = self.request("subscribe");
+        debug!("gemini subscribe - {}", subscribe.as_str());
+
+        self.state.lock().seeded = false;
+
+        let request = Message::Text(subscribe);
+        self.write(request)
+    }
+
+    fn unsubscribe(&self) -> Result<()> {
+        let unsubscribe = self.request("unsubscribe");
+        debug!("gemini unsubscribe - {}", unsubscribe.as_str());
+
+        let request = Message::Text(unsubscribe);
+        self.write(request)
+    }
+
+    fn summary(&self) -> Result<Summary> {
+        loop {
+            let message = frame::read_text(|| self.read())?;
+
+            if let Response::L2Updates { changes, .. } = serde_json::from_str(message.as_str())? {
+                let mut state = self.state.lock();
+
+                // the first update after subscribing is the whole book
+                if !state.seeded {
+                    state.book.clear();
+                    state.seeded = true;
+                }
+
+                for change in changes {
+                    let side = match change[0].as_str() {
+                        "buy" => Side::Bid,
+                        "sell" => Side::Ask,
+                        other => return Err(anyhow!("unexpected side {}", other)),
+                    };
+                    state
+                        .book
+                        .update(side, change[1].parse()?, change[2].parse()?);
+                }
+
+                return Ok(state.book.summary(self.name()));
+            }
+        }
+    }
+}
+
+impl<T: Transport> Gemini<T> {
+    pub fn new(config: ConfigRef, connection: u64) -> Result<Self> {
+        let url = config.gemini_url();
+        info!("gemini connect - {}", url);
+
+        let transport = T::connect(&config, url)?;
+
+        Ok(Self::with_transport(config, connection, transport))
+    }
+
+    /// Provider talking over an already connected transport
+    pub fn with_transport(config: ConfigRef, connection: u64, transport: T) -> Self {
+        Self {
+            recorder: Recorder::open(&config, "gemini", connection),
+            config,
+            transport,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    fn request(&self, kind: &str) -> String {
+        format!(
+            "{{\"type\":\"{}\",\"subscriptions\":[{{\"name\":\"l2\",\"symbols\":[\"{}\"]}}]}}",
+            kind,
+            self.config.pair().to_uppercase()
+        )
+    }
+
+    fn write(&self, request: Message) -> Result<()> {
+        self.transport.send(request)
+    }
+
+    fn read(&self) -> Result<Message> {
+        let message = self.transport.receive()?;
+        self.recorder.record(&message);
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{config::Config, transport::Memory};
+    use std::sync::Arc;
+    use url::Url;
+
+    fn memory(frames: &[&str]) -> (Gemini<Arc<Memory>>, Arc<Memory>) {
+        let memory = Arc::new(Memory::default());
+        for frame in frames {
+            memory.push_text(frame);
+        }
+        let provider = Gemini::with_transport(Config::as_ref(), 0, Arc::clone(&memory));
+        (provider, memory)
+    }
+
+    #[test]
+    fn test_connect_well() {
+        let provider = Gemini::<Arc<Memory>>::new(Config::as_ref(), 0).unwrap();
+        let memory = Arc::clone(&provider.transport);
+
+        assert_eq!(provider.name(), "Gemini");
+        assert_eq!(
+            memory.url(),
+            Some(&Url::parse("wss://api.gemini.com/v2/marketdata").unwrap())
+        );
+
+        drop(provider);
+        assert!(memory.is_closed());
+    }
+
+    #[test]
+    fn test_connect_fail() {
+        let config = Config::load_from(["algo", "--gemini-url", "ws://127.0.0.1:1"]).unwrap();
+
+        assert!(Gemini::<Tungstenite>::new(Arc::new(config), 0).is_err());
+    }
+
+    #[test]
+    fn test_subscribe_well() {
+        let (provider, memory) = memory(&[]);
+
+        assert!(provider.subscribe().is_ok());
+        assert_eq!(
+            memory.sent(),
+            [Message::Text(String::from(
+                "{\"type\":\"subscribe\",\"subscriptions\":[{\"name\":\"l2\",\"symbols\":[\"ETHBTC\"]}]}"
+            ))]
+        );
+    }
+
+    #[test]
+    fn test_unsubscribe_well() {
+        let (provider, memory) = memory(&[]);
+
+        assert!(provider.unsubscribe().is_ok());
+        assert_eq!(
+            memory.sent(),
+            [Message::Text(String::from(
+                "{\"type\":\"unsubscribe\",\"subscriptions\":[{\"name\":\"l2\",\"symbols\":[\"ETHBTC\"]}]}"
+            ))]
+        );
+    }
+
+    #[test]
+    fn test_summary_full_book_then_updates() -> Result<()> {
+        let (provider, _memory) = memory(&[
+            r#"{"type":"l2_updates","symbol":"ETHBTC","changes":[["buy","0.0646","1.2"],["buy","0.0645","2"],["sell","0.0648","0.5"],["sell","0.0649","0.4"]],"trades":[],"auction_events":[]}"#,
+            r#"{"type":"heartbeat","timestamp":1682624742462}"#,
+            r#"{"type":"trade","symbol":"ETHBTC","event_id":1,"timestamp":1682624742462,"price":"0.0647","quantity":"0.1","side":"buy"}"#,
+            r#"{"type":"l2_updates","symbol":"ETHBTC","changes":[["buy","0.0646","0"],["sell","0.0647","0.7"]]}"#,
+        ]);
+        provider.subscribe()?;
+
+        let summary = provider.summary()?;
+
+        assert_eq!(summary.bids.len(), 2);
+        assert_eq!(summary.bids[0].exchange, "Gemini");
+        assert_eq!(summary.bids[0].price, 0.0646);
+        assert_eq!(summary.bids[0].amount, 1.2);
+        assert_eq!(summary.asks.len(), 2);
+        assert_eq!(summary.asks[0].price, 0.0648);
+
+        let summary = provider.summary()?;
+
+        assert_eq!(summary.bids.len(), 1);
+        assert_eq!(summary.bids[0].price, 0.0645);
+        assert_eq!(summary.asks.len(), 3);
+        assert_eq!(summary.asks[0].price, 0.0647);
+        assert_eq!(summary.asks[0].amount, 0.7);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_unexpected_side() {
+        let (provider, _memory) = memory(&[
+            r#"{"type":"l2_updates","symbol":"ETHBTC","changes":[["hold","0.0646","1.2"]]}"#,
+        ]);
+
+        assert!(provider.summary().is_err());
+    }
+}
@@ -0,0 +1,15 @@
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    /// Changes are `[side, price, amount]` tuples, side being `buy` or `sell`
+    L2Updates {
+        #[serde(rename = "symbol")]
+        _symbol: String,
+        changes: Vec<[String; 3]>,
+    },
+    /// Heartbeats, trades and auction events
+    #[serde(other)]
+    Other,
+}
@@ -0,0 +1,224 @@
+use anyhow::{anyhow, Context, Result};
+use common::pair;
+use serde::Deserialize;
+use serde_json::Value;
+use std::{fs, path::Path};
+use url::Url;
+
+/// `[price, amount]` rows of the bid and ask sides
+pub type Levels = (Vec<[f64; 2]>, Vec<[f64; 2]>);
+
+/// Declarative description of an exchange feed, see the crate README
+#[derive(Deserialize, Clone)]
+pub struct Definition {
+    name: String,
+    url: String,
+    symbol: Option<String>,
+    subscribe: Option<String>,
+    unsubscribe: Option<String>,
+    #[serde(default)]
+    mode: Mode,
+    bids: String,
+    asks: String,
+    price: String,
+    amount: String,
+    snapshot: Option<Matcher>,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Every frame is the whole book
+    #[default]
+    Snapshot,
+    /// Frames only carry the changed levels
+    Diff,
+}
+
+#[derive(Deserialize, Clone)]
+struct Matcher {
+    pointer: String,
+    value: Value,
+}
+
+impl Definition {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        Self::parse(content.as_str())
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn url(&self, pair: &str) -> Result<Url> {
+        Ok(Url::parse(self.render(self.url.as_str(), pair)?.as_str())?)
+    }
+
+    pub fn subscribe(&self, pair: &str) -> Result<Option<String>> {
+        self.subscribe
+            .as_ref()
+            .map(|t| self.render(t, pair))
+            .transpose()
+    }
+
+    pub fn unsubscribe(&self, pair: &str) -> Result<Option<String>> {
+        self.unsubscribe
+            .as_ref()
+            .map(|t| self.render(t, pair))
+            .transpose()
+    }
+
+    /// Whether a diff frame replaces the whole book
+    pub fn is_snapshot(&self, frame: &Value) -> bool {
+        match &self.snapshot {
+            Some(matcher) => frame.pointer(matcher.pointer.as_str()) == Some(&matcher.value),
+            None => false,
+        }
+    }
+
+    /// Bids and asks of a frame, `None` when it carries no book
+    pub fn levels(&self, frame: &Value) -> Result<Option<Levels>> {
+        let bids = frame.pointer(self.bids.as_str());
+        let asks = frame.pointer(self.asks.as_str());
+
+        if bids.is_none() && asks.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some((self.side(bids)?, self.side(asks)?)))
+    }
+
+    fn side(&self, levels: Option<&Value>) -> Result<Vec<[f64; 2]>> {
+        let levels = match levels {
+            Some(Value::Array(levels)) => levels,
+            Some(other) => return Err(anyhow!("levels are not an array: {}", other)),
+            None => return Ok(vec![]),
+        };
+
+        levels
+            .iter()
+            .map(|level| {
+                Ok([
+                    number(level, self.price.as_str())?,
+                    number(level, self.amount.as_str())?,
+                ])
+            })
+            .collect()
+    }
+
+    /// Expands `{symbol}`, itself a template of the pair, then the pair placeholders
+    fn render(&self, template: &str, pair: &str) -> Result<String> {
+        let template = template.replace("{symbol}", self.symbol.as_deref().unwrap_or(pair));
+
+        let mut rendered = template
+            .replace("{pair}", pair.to_lowercase().as_str())
+            .replace("{PAIR}", pair.to_uppercase().as_str());
+
+        if ["{base}", "{quote}", "{BASE}", "{QUOTE}"]
+            .iter()
+            .any(|placeholder| rendered.contains(placeholder))
+        {
+            let (base, quote) =
+                pair::split(pair).ok_or_else(|| anyhow!("{} has no known quote currency", pair))?;
+
+            rendered = rendered
+                .replace("{base}", base.as_str())
+                .replace("{quote}", quote.as_str())
+                .replace("{BASE}", base.to_uppercase().as_str())
+                .replace("{QUOTE}", quote.to_uppercase().as_str());
+        }
+
+        Ok(rendered)
+    }
+}
+
+/// Exchanges quote either JSON numbers or strings
+fn number(level: &Value, pointer: &str) -> Result<f64> {
+    match level.pointer(pointer) {
+        Some(Value::Number(number)) => number
+            .as_f64()
+            .ok_or_else(|| anyhow!("{} is not a float", number)),
+        Some(Value::String(text)) => Ok(text.parse()?),
+        _ => Err(anyhow!("{} not found in {}", pointer, level)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kraken_definition() -> Result<()> {
+        let definition = Definition::parse(include_str!("../definitions/kraken.toml"))?;
+
+        assert_eq!(definition.name(), "Kraken");
+        assert!(definition.mode() == Mode::Diff);
+        assert_eq!(definition.url("ethbtc")?.as_str(), "wss://ws.kraken.com/v2");
+        assert_eq!(
+            definition.subscribe("ethbtc")?.unwrap(),
+            r#"{"method":"subscribe","params":{"channel":"book","symbol":["ETH/BTC"],"depth":25}}"#
+        );
+        assert_eq!(
+            definition.unsubscribe("btcusdt")?.unwrap(),
+            r#"{"method":"unsubscribe","params":{"channel":"book","symbol":["BTC/USDT"],"depth":25}}"#
+        );
+        assert!(definition.subscribe("ethgbp").is_err());
+
+        let frame: Value = serde_json::from_str(
+            r#"{"channel":"book","type":"snapshot","data":[{"symbol":"ETH/BTC","bids":[{"price":0.0646,"qty":1.5}],"asks":[{"price":0.0648,"qty":0.5}],"checksum":1}]}"#,
+        )?;
+
+        assert!(definition.is_snapshot(&frame));
+        assert_eq!(
+            definition.levels(&frame)?,
+            Some((vec![[0.0646, 1.5]], vec![[0.0648, 0.5]]))
+        );
+
+        let ack: Value = serde_json::from_str(r#"{"method":"subscribe","success":true}"#)?;
+        assert_eq!(definition.levels(&ack)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_templates() -> Result<()> {
+        let definition = Definition::parse(
+            r#"
+            name = "Test"
+            url = "wss://example.com/ws/{pair}@depth"
+            subscribe = '{"symbol":"{PAIR}"}'
+            bids = "/b"
+            asks = "/a"
+            price = "/0"
+            amount = "/1"
+            "#,
+        )?;
+
+        assert!(definition.mode() == Mode::Snapshot);
+        assert_eq!(
+            definition.url("ETHbtc")?.as_str(),
+            "wss://example.com/ws/ethbtc@depth"
+        );
+        assert_eq!(
+            definition.subscribe("ethbtc")?.unwrap(),
+            r#"{"symbol":"ETHBTC"}"#
+        );
+        assert!(definition.unsubscribe("ethbtc")?.is_none());
+
+        let frame: Value = serde_json::from_str(r#"{"b":[["0.1","2"]],"a":[[0.2,"x"]]}"#)?;
+        assert!(definition.levels(&frame).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+pub mod definition;
+pub mod provider;
+
+pub use definition::Definition;
+pub use provider::Generic;
+
+use anyhow::{bail, Result};
+use common::{chaos::ChaosTransport, registry::Registry, transport::Tungstenite};
+use std::path::PathBuf;
+
+/// Registers one provider per definition, under the lowercase definition name
+///
+/// A definition may not take the name of an exchange already registered, built-in or defined.
+pub fn register(registry: &mut Registry, definitions: &[PathBuf]) -> Result<()> {
+    for path in definitions {
+        let definition = Definition::load(path)?;
+        if registry.contains(definition.name()) {
+            bail!(
+                "{} defines {}, which is already registered",
+                path.display(),
+                definition.name()
+            );
+        }
+        // interned once per definition, providers are created again on every reconnect
+        let name: &'static str = Box::leak(definition.name().to_string().into_boxed_str());
+
+        registry.register(name, move |config, connection| {
+            let definition = definition.clone();
+            match config.chaos(name) {
+                true => Ok(Box::new(Generic::<ChaosTransport<Tungstenite>>::new(
+                    config, connection, name, definition,
+                )?)),
+                false => Ok(Box::new(Generic::<Tungstenite>::new(
+                    config, connection, name, definition,
+                )?)),
+            }
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_register() -> Result<()> {
+        let kraken = Path::new(env!("CARGO_MANIFEST_DIR")).join("definitions/kraken.toml");
+
+        let mut registry = Registry::default();
+        register(&mut registry, std::slice::from_ref(&kraken))?;
+        assert!(registry.contains("kraken"));
+
+        let error = register(&mut registry, &[kraken]).unwrap_err();
+        assert!(error
+            .to_string()
+            .ends_with("defines Kraken, which is already registered"));
+
+        Ok(())
+    }
+}
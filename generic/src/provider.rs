@@ -0,0 +1,270 @@
+use crate::definition::{Definition, Mode};
+use anyhow::Result;
+use common::{
+    book::{Book, Side},
+    frame,
+    orderbook::{Level, Summary},
+    recorder::Recorder,
+    transport::Tungstenite,
+    ConfigRef, Provider, Transport,
+};
+use log::{debug, info};
+use parking_lot::Mutex;
+use serde_json::Value;
+use tungstenite::Message;
+
+pub struct Generic<T: Transport = Tungstenite> {
+    name: &'static str,
+    config: ConfigRef,
+    definition: Definition,
+    transport: T,
+    recorder: Recorder,
+    book: Mutex<Book>,
+}
+
+impl<T: Transport> Drop for Generic<T> {
+    fn drop(&mut self) {
+        info!("{} disconnect", self.name);
+        self.transport.close();
+    }
+}
+
+impl<T: Transport> Provider for Generic<T> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn subscribe(&self) -> Result<()> {
+        match self.definition.subscribe(self.config.pair())? {
+            Some(subscribe) => {
+                debug!("{} subscribe - {}", self.name, subscribe.as_str());
+                self.book.lock().clear();
+                self.write(Message::Text(subscribe))
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn unsubscribe(&self) -> Result<()> {
+        match self.definition.unsubscribe(self.config.pair())? {
+            Some(unsubscribe) => {
+                debug!("{} unsubscribe - {}", self.name, unsubscribe.as_str());
+                self.write(Message::Text(unsubscribe))
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn summary(&self) -> Result<Summary> {
+        loop {
+            let message = frame::read_text(|| self.read())?;
+            let frame: Value = serde_json::from_str(message.as_str())?;
+
+            let (bids, asks) = match self.definition.levels(&frame)? {
+                Some(levels) => levels,
+                None => continue,
+            };
+
+            return Ok(match self.definition.mode() {
+                Mode::Snapshot => {
+                    let level = |order: [f64; 2]| Level {
+                        exchange: String::from(self.name),
+                        price: order[0],
+                        amount: order[1],
+                        ..Default::default()
+                    };
+
+                    Summary {
+                        bids: bids.into_iter().map(level).collect(),
+                        asks: asks.into_iter().map(level).collect(),
+                        ..Default::default()
+                    }
+                }
+                Mode::Diff => {
+                    let mut book = self.book.lock();
+
+                    if self.definition.is_snapshot(&frame) {
+                        book.clear();
+                    }
+
+                    for order in bids {
+                        book.update(Side::Bid, order[0], order[1]);
+                    }
+                    for order in asks {
+                        book.update(Side::Ask, order[0], order[1]);
+                    }
+
+                    book.summary(self.name)
+                }
+            });
+        }
+    }
+}
+
+impl<T: Transport> Generic<T> {
+    /// Provider for a definition, published under `name`
+    pub fn new(
+        config: ConfigRef,
+        connection: u64,
+        name: &'static str,
+        definition: Definition,
+    ) -> Result<Self> {
+        let url = definition.url(config.pair())?;
+        info!("{} connect - {}", name, url);
+
+        let transport = T::connect(&config, &url)?;
+
+        Ok(Self::with_transport(
+            config, connection, name, definition, transport,
+        ))
+    }
+
+    /// Provider talking over an already connected transport
+    pub fn with_transport(
+        config: ConfigRef,
+        connection: u64,
+        name: &'static str,
+        definition: Definition,
+        transport: T,
+    ) -> Self {
+        Self {
+            name,
+            recorder: Recorder::open(&config, name, connection),
+            config,
+            definition,
+            transport,
+            book: Mutex::new(Book::default()),
+        }
+    }
+
+    fn write(&self, request: Message) -> Result<()> {
+        self.transport.send(request)
+    }
+
+    fn read(&self) -> Result<Message> {
+        let message = self.transport.receive()?;
+        self.recorder.record(&message);
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{config::Config, transport::Memory};
+    use std::sync::Arc;
+    use url::Url;
+
+    const BINANCE: &str = r#"
+        name = "Binance"
+        url = "wss://stream.binance.com:9443/ws/{pair}@depth20@100ms"
+        bids = "/bids"
+        asks = "/asks"
+        price = "/0"
+        amount = "/1"
+    "#;
+
+    fn memory(
+        name: &'static str,
+        definition: Definition,
+        frames: &[&str],
+    ) -> (Generic<Arc<Memory>>, Arc<Memory>) {
+        let memory = Arc::new(Memory::default());
+        for frame in frames {
+            memory.push_text(frame);
+        }
+        let provider =
+            Generic::with_transport(Config::as_ref(), 0, name, definition, Arc::clone(&memory));
+        (provider, memory)
+    }
+
+    #[test]
+    fn test_connect_well() -> Result<()> {
+        let provider = Generic::<Arc<Memory>>::new(
+            Config::as_ref(),
+            0,
+            "Binance",
+            Definition::parse(BINANCE)?,
+        )?;
+        let memory = Arc::clone(&provider.transport);
+
+        assert_eq!(provider.name(), "Binance");
+        assert_eq!(
+            memory.url(),
+            Some(&Url::parse(
+                "wss://stream.binance.com:9443/ws/ethbtc@depth20@100ms"
+            )?)
+        );
+
+        // nothing to send without templates
+        assert!(provider.subscribe().is_ok());
+        assert!(provider.unsubscribe().is_ok());
+        assert!(memory.sent().is_empty());
+
+        drop(provider);
+        assert!(memory.is_closed());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_snapshot() -> Result<()> {
+        let (provider, _memory) = memory(
+            "Binance",
+            Definition::parse(BINANCE)?,
+            &[
+                r#"{"result":null,"id":1}"#,
+                r#"{"lastUpdateId":1,"bids":[["0.06466","0.5"],["0.06465","0.7"]],"asks":[["0.06468","0.4"]]}"#,
+            ],
+        );
+
+        let summary = provider.summary()?;
+
+        assert_eq!(summary.bids.len(), 2);
+        assert_eq!(summary.bids[0].exchange, "Binance");
+        assert_eq!(summary.bids[0].price, 0.06466);
+        assert_eq!(summary.bids[1].amount, 0.7);
+        assert_eq!(summary.asks.len(), 1);
+        assert_eq!(summary.asks[0].price, 0.06468);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_diff() -> Result<()> {
+        let (provider, memory) = memory(
+            "Kraken",
+            Definition::parse(include_str!("../definitions/kraken.toml"))?,
+            &[
+                r#"{"method":"subscribe","success":true}"#,
+                r#"{"channel":"book","type":"snapshot","data":[{"symbol":"ETH/BTC","bids":[{"price":0.0646,"qty":1.5},{"price":0.0645,"qty":2}],"asks":[{"price":0.0648,"qty":0.5}]}]}"#,
+                r#"{"channel":"book","type":"update","data":[{"symbol":"ETH/BTC","bids":[{"price":0.0646,"qty":0}],"asks":[{"price":0.0647,"qty":0.1}]}]}"#,
+            ],
+        );
+        provider.subscribe()?;
+
+        assert_eq!(
+            memory.sent(),
+            [Message::Text(String::from(
+                r#"{"method":"subscribe","params":{"channel":"book","symbol":["ETH/BTC"],"depth":25}}"#
+            ))]
+        );
+
+        let summary = provider.summary()?;
+
+        assert_eq!(summary.bids.len(), 2);
+        assert_eq!(summary.bids[0].exchange, "Kraken");
+        assert_eq!(summary.bids[0].price, 0.0646);
+        assert_eq!(summary.asks.len(), 1);
+
+        let summary = provider.summary()?;
+
+        assert_eq!(summary.bids.len(), 1);
+        assert_eq!(summary.bids[0].price, 0.0645);
+        assert_eq!(summary.asks.len(), 2);
+        assert_eq!(summary.asks[0].price, 0.0647);
+        assert_eq!(summary.asks[0].amount, 0.1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+pub(crate) mod orderbook;
+pub(crate) mod response;
+
+pub mod provider;
+
+pub use provider::Htx;
+
+use common::{chaos::ChaosTransport, registry::Registry, transport::Tungstenite};
+
+/// Registers the provider under `htx`
+pub fn register(registry: &mut Registry) {
+    registry.register("htx", |config, connection| match config.chaos("htx") {
+        true => Ok(Box::new(Htx::<ChaosTransport<Tungstenite>>::new(
+            config, connection,
+        )?)),
+        false => Ok(Box::new(Htx::<Tungstenite>::new(config, connection)?)),
+    });
+}
@@ -0,0 +1,19 @@
+use serde::Deserialize;
+use std::slice::Iter;
+
+#[derive(Deserialize)]
+pub struct OrderBook {
+    #[serde(rename = "ts")]
+    _timestamp: u64,
+    bids: Vec<[f64; 2]>,
+    asks: Vec<[f64; 2]>,
+}
+
+impl OrderBook {
+    pub fn asks(&self) -> Iter<'_, [f64; 2]> {
+        self.asks.iter()
+    }
+    pub fn bids(&self) -> Iter<'_, [f64; 2]> {
+        self.bids.iter()
+    }
+}
@@ -0,0 +1,265 @@
+use crate::response::Response;
+use anyhow::{anyhow, Result};
+use common::{
+    frame,
+    orderbook::{Level, Summary},
+    recorder::Recorder,
+    transport::Tungstenite,
+    ConfigRef, Provider, Transport,
+};
+use log::{debug, info};
+use tungstenite::Message;
+
+pub struct Htx<T: Transport = Tungstenite> {
+    config: ConfigRef,
+    transport: T,
+    recorder: Recorder,
+}
+
+impl<T: Transport> Drop for Htx<T> {
+    fn drop(&mut self) {
+        info!("htx disconnect");
+        self.transport.close();
+    }
+}
+
+impl<T: Transport> Provider for Htx<T> {
+    fn name(&self) -> &'static str {
+        "HTX"
+    }
+
+    fn subscribe(&self) -> Result<()> {
+        let subscribe = format!(
+            "{{\"sub\":\"{}\",\"id\":\"{}\"}}",
+            self.topic(),
+            self.config.pair()
+        );
+        debug!("htx subscribe - {}", subscribe.as_str());
+
+        let request = Message::Text(subscribe);
+        self.write(request)?;
+
+        self.status()
+    }
+
+    fn unsubscribe(&self) -> Result<()> {
+        let unsubscribe = format!(
+            "{{\"unsub\":\"{}\",\"id\":\"{}\"}}",
+            self.topic(),
+            self.config.pair()
+        );
+        debug!("htx unsubscribe - {}", unsubscribe.as_str());
+
+        let request = Message::Text(unsubscribe);
+        self.write(request)?;
+
+        self.status()
+    }
+
+    fn summary(&self) -> Result<Summary> {
+        loop {
+            if let Response::Tick { tick, .. } = self.next()? {
+                let mut summary = Summary::default();
+
+                for order in tick.asks() {
+                    let level = Level {
+                        exchange: String::from(self.name()),
+                        price: order[0],
+                        amount: order[1],
+                        ..Default::default()
+                    };
+                    summary.asks.push(level);
+                }
+
+                for order in tick.bids() {
+                    let level = Level {
+                        exchange: String::from(self.name()),
+                        price: order[0],
+                        amount: order[1],
+                        ..Default::default()
+                    };
+                    summary.bids.push(level);
+                }
+
+                return Ok(summary);
+            }
+        }
+    }
+}
+
+impl<T: Transport> Htx<T> {
+    pub fn new(config: ConfigRef, connection: u64) -> Result<Self> {
+        let url = config.htx_url();
+        info!("htx connect - {}", url);
+
+        let transport = T::connect(&config, url)?;
+
+        Ok(Self::with_transport(config, connection, transport))
+    }
+
+    /// Provider talking over an already connected transport
+    pub fn with_transport(config: ConfigRef, connection: u64, transport: T) -> Self {
+        let recorder = Recorder::open(&config, "htx", connection);
+
+        Self {
+            config,
+            transport,
+            recorder,
+        }
+    }
+
+    fn topic(&self) -> String {
+        format!("market.{}.depth.step0", self.config.pair())
+    }
+
+    /// Waits for the (un)subscription acknowledge, ticks still in flight are discarded
+    fn status(&self) -> Result<()> {
+        loop {
+            if let Response::Status { status, error } = self.next()? {
+                info!("htx status - {}", status);
+
+                return match status.as_str() {
+                    "ok" => Ok(()),
+                    _ => Err(anyhow!(error.unwrap_or(status))),
+                };
+            }
+        }
+    }
+
+    /// Next response that is not a heartbeat, heartbeats are answered in place
+    fn next(&self) -> Result<Response> {
+        loop {
+            let message = frame::read_text(|| self.read())?;
+
+            match serde_json::from_str(message.as_str())? {
+                Response::Ping { ping } => {
+                    self.write(Message::Text(format!("{{\"pong\":{}}}", ping)))?;
+                }
+                response => return Ok(response),
+            }
+        }
+    }
+
+    fn write(&self, request: Message) -> Result<()> {
+        self.transport.send(request)
+    }
+
+    fn read(&self) -> Result<Message> {
+        let message = self.transport.receive()?;
+        self.recorder.record(&message);
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{config::Config, transport::Memory};
+    use flate2::{write::GzEncoder, Compression};
+    use std::{io::Write, sync::Arc};
+    use url::Url;
+
+    fn gzip(text: &str) -> Message {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        Message::Binary(encoder.finish().unwrap())
+    }
+
+    fn memory() -> (Htx<Arc<Memory>>, Arc<Memory>) {
+        let memory = Arc::new(Memory::default());
+        let provider = Htx::with_transport(Config::as_ref(), 0, Arc::clone(&memory));
+        (provider, memory)
+    }
+
+    #[test]
+    fn test_connect_well() {
+        let provider = Htx::<Arc<Memory>>::new(Config::as_ref(), 0).unwrap();
+        let memory = Arc::clone(&provider.transport);
+
+        assert_eq!(
+            memory.url(),
+            Some(&Url::parse("wss://api.huobi.pro/ws").unwrap())
+        );
+
+        drop(provider);
+        assert!(memory.is_closed());
+    }
+
+    #[test]
+    fn test_connect_fail() {
+        let config = Config::load_from(["algo", "--htx-url", "ws://127.0.0.1:1"]).unwrap();
+
+        assert!(Htx::<Tungstenite>::new(Arc::new(config), 0).is_err());
+    }
+
+    #[test]
+    fn test_name() {
+        let (provider, _memory) = memory();
+        assert_eq!(provider.name(), "HTX");
+    }
+
+    #[test]
+    fn test_subscribe_well() {
+        let (provider, memory) = memory();
+        memory.push(gzip(
+            r#"{"id":"ethbtc","status":"ok","subbed":"market.ethbtc.depth.step0","ts":1682624742462}"#,
+        ));
+
+        assert!(provider.subscribe().is_ok());
+        assert_eq!(
+            memory.sent(),
+            [Message::Text(String::from(
+                "{\"sub\":\"market.ethbtc.depth.step0\",\"id\":\"ethbtc\"}"
+            ))]
+        );
+    }
+
+    #[test]
+    fn test_subscribe_rejected() {
+        let (provider, memory) = memory();
+        memory.push(gzip(
+            r#"{"status":"error","err-code":"bad-request","err-msg":"invalid topic","ts":1682624742462}"#,
+        ));
+
+        assert_eq!(
+            provider.subscribe().unwrap_err().to_string(),
+            "invalid topic"
+        );
+    }
+
+    #[test]
+    fn test_summary_answers_ping() -> Result<()> {
+        let (provider, memory) = memory();
+        memory.push(gzip(r#"{"ping":1682624742462}"#));
+        memory.push(gzip(
+            r#"{"ch":"market.ethbtc.depth.step0","ts":1682624742462,"tick":{"bids":[[0.06466182,0.5],[0.06465586,0.77986816]],"asks":[[0.06468051,0.5],[0.06468374,0.4]],"version":1,"ts":1682624742461}}"#,
+        ));
+
+        let summary = provider.summary()?;
+
+        assert_eq!(
+            memory.sent(),
+            [Message::Text(String::from("{\"pong\":1682624742462}"))]
+        );
+
+        assert_eq!(summary.bids.len(), 2);
+
+        assert_eq!(summary.bids[0].exchange, "HTX");
+        assert_eq!(summary.bids[0].price, 0.06466182);
+        assert_eq!(summary.bids[0].amount, 0.5);
+
+        assert_eq!(summary.bids[1].price, 0.06465586);
+        assert_eq!(summary.bids[1].amount, 0.77986816);
+
+        assert_eq!(summary.asks.len(), 2);
+
+        assert_eq!(summary.asks[0].exchange, "HTX");
+        assert_eq!(summary.asks[0].price, 0.06468051);
+        assert_eq!(summary.asks[0].amount, 0.5);
+
+        assert_eq!(summary.asks[1].price, 0.06468374);
+        assert_eq!(summary.asks[1].amount, 0.4);
+
+        Ok(())
+    }
+}
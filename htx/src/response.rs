@@ -0,0 +1,20 @@
+use crate::orderbook::OrderBook;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Response {
+    Ping {
+        ping: u64,
+    },
+    Tick {
+        #[serde(rename = "ch")]
+        _channel: String,
+        tick: OrderBook,
+    },
+    Status {
+        status: String,
+        #[serde(rename = "err-msg", default)]
+        error: Option<String>,
+    },
+}
@@ -0,0 +1,20 @@
+pub(crate) mod response;
+pub(crate) mod rest;
+
+pub mod provider;
+
+pub use provider::Kucoin;
+
+use common::{chaos::ChaosTransport, registry::Registry, transport::Tungstenite};
+
+/// Registers the provider under `kucoin`
+pub fn register(registry: &mut Registry) {
+    registry.register("kucoin", |config, connection| {
+        match config.chaos("kucoin") {
+            true => Ok(Box::new(Kucoin::<ChaosTransport<Tungstenite>>::new(
+                config, connection,
+            )?)),
+            false => Ok(Box::new(Kucoin::<Tungstenite>::new(config, connection)?)),
+        }
+    });
+}
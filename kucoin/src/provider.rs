@@ -0,0 +1,472 @@
+use crate::response::{Bullet, Response, Update};
+use anyhow::{anyhow, bail, Result};
+use common::{
+    book::{Book, Side},
+    frame,
+    orderbook::Summary,
+    pair,
+    recorder::Recorder,
+    transport::Tungstenite,
+    ConfigRef, Provider, Transport,
+};
+use log::{debug, info, warn};
+use mockall_double::double;
+use parking_lot::Mutex;
+use std::{
+    process,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+use tungstenite::Message;
+use url::Url;
+
+#[double]
+use crate::rest::Rest;
+
+pub struct Kucoin<T: Transport = Tungstenite> {
+    _config: ConfigRef,
+    rest: Rest,
+    transport: Arc<T>,
+    recorder: Recorder,
+    symbol: String,
+    requests: AtomicU64,
+    state: Mutex<State>,
+    /// Stops the keepalive once dropped
+    _keepalive: Sender<()>,
+}
+
+struct State {
+    book: Book,
+    sequence: u64,
+}
+
+impl<T: Transport> Drop for Kucoin<T> {
+    fn drop(&mut self) {
+        info!("kucoin disconnect");
+        self.transport.close();
+    }
+}
+
+impl<T: Transport> Provider for Kucoin<T> {
+    fn name(&self) -> &'static str {
+        "KuCoin"
+    }
+
+    fn subscribe(&self) -> Result<()> {
+        let subscribe = self.request("subscribe");
+        debug!("kucoin subscribe - {}", subscribe.as_str());
+
+        self.write(Message::Text(subscribe))?;
+        self.acknowledge()?;
+
+        self.seed()
+    }
+
+    fn unsubscribe(&self) -> Result<()> {
+        let unsubscribe = self.request("unsubscribe");
+        debug!("kucoin unsubscribe - {}", unsubscribe.as_str());
+
+        self.write(Message::Text(unsubscribe))?;
+        self.acknowledge()
+    }
+
+    fn summary(&self) -> Result<Summary> {
+        loop {
+            match self.next()? {
+                Response::Message { data } if self.apply(&data)? => {
+                    let state = self.state.lock();
+                    return Ok(state.book.summary(self.name()));
+                }
+                Response::Error { data } => return Err(anyhow!(data)),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<T: Transport + 'static> Kucoin<T> {
+    pub fn new(config: ConfigRef, connection: u64) -> Result<Self> {
+        let rest = Rest::new(config.kucoin_url());
+
+        let (url, ping_interval) = endpoint(&rest.bullet()?)?;
+        info!("kucoin connect - {}", url.origin().ascii_serialization());
+
+        let transport = T::connect(&config, &url)?;
+
+        Self::with_transport(config, connection, rest, transport, ping_interval)
+    }
+
+    /// Provider talking over an already connected transport, once welcomed
+    fn with_transport(
+        config: ConfigRef,
+        connection: u64,
+        rest: Rest,
+        transport: T,
+        ping_interval: Duration,
+    ) -> Result<Self> {
+        let transport = Arc::new(transport);
+
+        let provider = Self {
+            symbol: symbol(config.pair()),
+            recorder: Recorder::open(&config, "kucoin", connection),
+            _config: config,
+            rest,
+            _keepalive: keepalive(Arc::clone(&transport), ping_interval),
+            transport,
+            requests: AtomicU64::new(1),
+            state: Mutex::new(State {
+                book: Book::default(),
+                sequence: 0,
+            }),
+        };
+
+        match provider.next()? {
+            Response::Welcome => Ok(provider),
+            _ => bail!("kucoin did not welcome"),
+        }
+    }
+}
+
+impl<T: Transport> Kucoin<T> {
+    fn request(&self, kind: &str) -> String {
+        format!(
+            "{{\"id\":\"{}\",\"type\":\"{}\",\"topic\":\"/market/level2:{}\",\"privateChannel\":false,\"response\":true}}",
+            self.requests.fetch_add(1, Ordering::Relaxed),
+            kind,
+            self.symbol
+        )
+    }
+
+    /// Waits for the (un)subscription acknowledge, updates still in flight are discarded
+    fn acknowledge(&self) -> Result<()> {
+        loop {
+            match self.next()? {
+                Response::Ack => return Ok(()),
+                Response::Error { data } => return Err(anyhow!(data)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Replaces the local book with the REST snapshot
+    fn seed(&self) -> Result<()> {
+        let snapshot = self.rest.snapshot(self.symbol.as_str())?;
+        let mut state = self.state.lock();
+
+        state.book.clear();
+        state.sequence = snapshot.sequence()?;
+
+        for order in snapshot.asks() {
+            state
+                .book
+                .update(Side::Ask, order[0].parse()?, order[1].parse()?);
+        }
+
+        for order in snapshot.bids() {
+            state
+                .book
+                .update(Side::Bid, order[0].parse()?, order[1].parse()?);
+        }
+
+        info!("kucoin seed - sequence {}", state.sequence);
+
+        Ok(())
+    }
+
+    /// Applies an update on top of the book, returns whether the book changed
+    fn apply(&self, update: &Update) -> Result<bool> {
+        let mut state = self.state.lock();
+
+        if update.sequence_end() <= state.sequence {
+            return Ok(false);
+        }
+
+        if update.sequence_start() > state.sequence + 1 {
+            warn!(
+                "kucoin gap - expected {} received {}",
+                state.sequence + 1,
+                update.sequence_start()
+            );
+            drop(state);
+            self.seed()?;
+            return Ok(false);
+        }
+
+        for (side, changes) in [(Side::Ask, update.asks()), (Side::Bid, update.bids())] {
+            for change in changes {
+                let price: f64 = change[0].parse()?;
+
+                if price != 0.0 && change[2].parse::<u64>()? > state.sequence {
+                    state.book.update(side, price, change[1].parse()?);
+                }
+            }
+        }
+
+        state.sequence = update.sequence_end();
+
+        Ok(true)
+    }
+
+    fn next(&self) -> Result<Response> {
+        let message = frame::read_text(|| self.read())?;
+        Ok(serde_json::from_str(message.as_str())?)
+    }
+
+    fn write(&self, request: Message) -> Result<()> {
+        self.transport.send(request)
+    }
+
+    fn read(&self) -> Result<Message> {
+        let message = self.transport.receive()?;
+        self.recorder.record(&message);
+        Ok(message)
+    }
+}
+
+/// KuCoin drops clients that stay silent longer than the ping interval, so pings go out on a timer
+/// whether or not anyone reads, until the returned sender is dropped
+fn keepalive<T: Transport + 'static>(transport: Arc<T>, interval: Duration) -> Sender<()> {
+    let (stop, stopped) = mpsc::channel::<()>();
+
+    thread::spawn(move || {
+        let mut pings = 0u64;
+
+        while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+            pings += 1;
+            let ping = format!("{{\"id\":\"ping-{}\",\"type\":\"ping\"}}", pings);
+
+            if let Err(e) = transport.send(Message::Text(ping)) {
+                warn!("kucoin ping failed - {}", e);
+                return;
+            }
+        }
+    });
+
+    stop
+}
+
+/// Websocket URL the bullet hands out, with its token, and how often to ping it
+fn endpoint(bullet: &Bullet) -> Result<(Url, Duration)> {
+    let server = bullet
+        .server()
+        .ok_or_else(|| anyhow!("kucoin offered no endpoint"))?;
+
+    let mut url = Url::parse(server.endpoint())?;
+    url.query_pairs_mut()
+        .append_pair("token", bullet.token())
+        .append_pair("connectId", format!("algo-{}", process::id()).as_str());
+
+    Ok((url, Duration::from_millis(server.ping_interval())))
+}
+
+/// KuCoin symbols split base and quote, `ethbtc` is `ETH-BTC`
+fn symbol(pair: &str) -> String {
+    match pair::split(pair) {
+        Some((base, quote)) => format!("{}-{}", base, quote).to_uppercase(),
+        None => pair.to_uppercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        response::{Envelope, Snapshot},
+        rest::MockRest,
+    };
+    use common::{config::Config, transport::Memory};
+    use mockall::predicate::eq;
+    use std::sync::Arc;
+
+    const WELCOME: &str = r#"{"id":"hQvf8jkno","type":"welcome"}"#;
+
+    fn bullet() -> Result<Bullet> {
+        serde_json::from_str::<Envelope<Bullet>>(
+            r#"{"code":"200000","data":{"token":"secret","instanceServers":[{"endpoint":"wss://ws-api-spot.kucoin.com/","encrypt":true,"protocol":"websocket","pingInterval":18000,"pingTimeout":10000}]}}"#,
+        )?
+        .into_data()
+    }
+
+    fn snapshot(_symbol: &str) -> Result<Snapshot> {
+        serde_json::from_str::<Envelope<Snapshot>>(
+            r#"{"code":"200000","data":{"sequence":"100","time":1682624742462,"bids":[["0.06466","1.5"],["0.06465","2.5"]],"asks":[["0.06468","0.5"],["0.06469","0.4"]]}}"#,
+        )?
+        .into_data()
+    }
+
+    fn rest(snapshots: usize) -> MockRest {
+        let mut mocked = MockRest::default();
+        mocked
+            .expect_snapshot()
+            .with(eq("ETH-BTC"))
+            .times(snapshots)
+            .returning(snapshot);
+        mocked
+    }
+
+    /// Provider welcomed over a memory transport scripted with the frames
+    fn memory(snapshots: usize, frames: &[&str]) -> Result<(Kucoin<Arc<Memory>>, Arc<Memory>)> {
+        let memory = Arc::new(Memory::default());
+        for frame in frames {
+            memory.push_text(frame);
+        }
+        let provider = Kucoin::with_transport(
+            Config::as_ref(),
+            0,
+            rest(snapshots),
+            Arc::clone(&memory),
+            Duration::from_secs(18),
+        )?;
+        Ok((provider, memory))
+    }
+
+    #[test]
+    fn test_symbol() {
+        assert_eq!(symbol("ethbtc"), "ETH-BTC");
+        assert_eq!(symbol("btcusdt"), "BTC-USDT");
+        assert_eq!(symbol("ETHUSDC"), "ETH-USDC");
+        assert_eq!(symbol("btc"), "BTC");
+    }
+
+    #[test]
+    fn test_endpoint() -> Result<()> {
+        let (url, ping_interval) = endpoint(&bullet()?)?;
+
+        assert!(url
+            .as_str()
+            .starts_with("wss://ws-api-spot.kucoin.com/?token=secret&connectId="));
+        assert_eq!(ping_interval, Duration::from_secs(18));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_connect_well() -> Result<()> {
+        let (provider, memory) = memory(0, &[WELCOME])?;
+        assert_eq!(provider.name(), "KuCoin");
+
+        drop(provider);
+        assert!(memory.is_closed());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keepalive() -> Result<()> {
+        let memory = Arc::new(Memory::default());
+        memory.push_text(WELCOME);
+        let provider = Kucoin::with_transport(
+            Config::as_ref(),
+            0,
+            rest(0),
+            Arc::clone(&memory),
+            Duration::from_millis(10),
+        )?;
+
+        // nobody reads, the pings go out all the same
+        thread::sleep(Duration::from_millis(100));
+        assert!(memory.sent().contains(&Message::Text(String::from(
+            "{\"id\":\"ping-1\",\"type\":\"ping\"}"
+        ))));
+
+        drop(provider);
+        let sent = memory.sent().len();
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(memory.sent().len(), sent);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_connect_without_welcome() {
+        assert!(memory(0, &[r#"{"id":"1","type":"pong"}"#]).is_err());
+    }
+
+    #[test]
+    fn test_subscribe_well() -> Result<()> {
+        let (provider, memory) = memory(1, &[WELCOME, r#"{"id":"1","type":"ack"}"#])?;
+
+        assert!(provider.subscribe().is_ok());
+        assert_eq!(
+            memory.sent(),
+            [Message::Text(String::from(
+                "{\"id\":\"1\",\"type\":\"subscribe\",\"topic\":\"/market/level2:ETH-BTC\",\"privateChannel\":false,\"response\":true}"
+            ))]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_subscribe_error() -> Result<()> {
+        let (provider, _memory) = memory(
+            0,
+            &[
+                WELCOME,
+                r#"{"id":"1","type":"error","code":404,"data":"topic /market/level2:ETH-BTC is not supported"}"#,
+            ],
+        )?;
+
+        assert_eq!(
+            provider.subscribe().unwrap_err().to_string(),
+            "topic /market/level2:ETH-BTC is not supported"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_applies_updates() -> Result<()> {
+        let (provider, _memory) = memory(
+            1,
+            &[
+                WELCOME,
+                r#"{"id":"1","type":"ack"}"#,
+                r#"{"type":"message","topic":"/market/level2:ETH-BTC","subject":"trade.l2update","data":{"changes":{"asks":[["0.06468","9","99"]],"bids":[]},"sequenceEnd":99,"sequenceStart":99,"symbol":"ETH-BTC","time":1682624742462}}"#,
+                r#"{"type":"message","topic":"/market/level2:ETH-BTC","subject":"trade.l2update","data":{"changes":{"asks":[["0.06468","0","101"],["0.06467","0.7","102"]],"bids":[["0.06466","3","102"]]},"sequenceEnd":102,"sequenceStart":101,"symbol":"ETH-BTC","time":1682624742463}}"#,
+            ],
+        )?;
+        provider.subscribe()?;
+
+        let summary = provider.summary()?;
+
+        assert_eq!(summary.asks.len(), 2);
+        assert_eq!(summary.asks[0].exchange, "KuCoin");
+        assert_eq!(summary.asks[0].price, 0.06467);
+        assert_eq!(summary.asks[0].amount, 0.7);
+        assert_eq!(summary.asks[1].price, 0.06469);
+
+        assert_eq!(summary.bids.len(), 2);
+        assert_eq!(summary.bids[0].price, 0.06466);
+        assert_eq!(summary.bids[0].amount, 3.0);
+        assert_eq!(summary.bids[1].price, 0.06465);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_reseeds_on_gap() -> Result<()> {
+        let (provider, _memory) = memory(
+            2,
+            &[
+                WELCOME,
+                r#"{"id":"1","type":"ack"}"#,
+                r#"{"type":"message","topic":"/market/level2:ETH-BTC","subject":"trade.l2update","data":{"changes":{"asks":[["0.06460","1","105"]],"bids":[]},"sequenceEnd":105,"sequenceStart":105,"symbol":"ETH-BTC","time":1682624742462}}"#,
+                r#"{"type":"message","topic":"/market/level2:ETH-BTC","subject":"trade.l2update","data":{"changes":{"asks":[],"bids":[["0.06466","3","101"]]},"sequenceEnd":101,"sequenceStart":101,"symbol":"ETH-BTC","time":1682624742463}}"#,
+            ],
+        )?;
+        provider.subscribe()?;
+
+        let summary = provider.summary()?;
+
+        assert_eq!(summary.asks[0].price, 0.06468);
+        assert_eq!(summary.bids[0].amount, 3.0);
+
+        Ok(())
+    }
+}
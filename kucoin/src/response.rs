@@ -0,0 +1,120 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::slice::Iter;
+
+/// REST answers are wrapped with a status code, "200000" means success
+#[derive(Deserialize)]
+pub struct Envelope<T> {
+    code: String,
+    #[serde(default)]
+    msg: Option<String>,
+    data: Option<T>,
+}
+
+impl<T> Envelope<T> {
+    pub fn into_data(self) -> Result<T> {
+        match (self.code.as_str(), self.data) {
+            ("200000", Some(data)) => Ok(data),
+            _ => Err(anyhow!(self.msg.unwrap_or(self.code))),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Bullet {
+    token: String,
+    #[serde(rename = "instanceServers")]
+    servers: Vec<Server>,
+}
+
+impl Bullet {
+    pub fn token(&self) -> &str {
+        self.token.as_str()
+    }
+
+    pub fn server(&self) -> Option<&Server> {
+        self.servers.first()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Server {
+    endpoint: String,
+    #[serde(rename = "pingInterval")]
+    ping_interval: u64,
+}
+
+impl Server {
+    pub fn endpoint(&self) -> &str {
+        self.endpoint.as_str()
+    }
+
+    /// Milliseconds between client pings
+    pub fn ping_interval(&self) -> u64 {
+        self.ping_interval
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Snapshot {
+    sequence: String,
+    bids: Vec<[String; 2]>,
+    asks: Vec<[String; 2]>,
+}
+
+impl Snapshot {
+    pub fn sequence(&self) -> Result<u64> {
+        Ok(self.sequence.parse()?)
+    }
+    pub fn asks(&self) -> Iter<'_, [String; 2]> {
+        self.asks.iter()
+    }
+    pub fn bids(&self) -> Iter<'_, [String; 2]> {
+        self.bids.iter()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Response {
+    Welcome,
+    Ack,
+    Pong,
+    Message {
+        data: Update,
+    },
+    Error {
+        #[serde(default)]
+        data: String,
+    },
+}
+
+#[derive(Deserialize)]
+pub struct Update {
+    changes: Changes,
+    #[serde(rename = "sequenceStart")]
+    sequence_start: u64,
+    #[serde(rename = "sequenceEnd")]
+    sequence_end: u64,
+}
+
+#[derive(Deserialize)]
+struct Changes {
+    asks: Vec<[String; 3]>,
+    bids: Vec<[String; 3]>,
+}
+
+impl Update {
+    pub fn sequence_start(&self) -> u64 {
+        self.sequence_start
+    }
+    pub fn sequence_end(&self) -> u64 {
+        self.sequence_end
+    }
+    pub fn asks(&self) -> Iter<'_, [String; 3]> {
+        self.changes.asks.iter()
+    }
+    pub fn bids(&self) -> Iter<'_, [String; 3]> {
+        self.changes.bids.iter()
+    }
+}
@@ -0,0 +1,42 @@
+use crate::response::{Bullet, Envelope, Snapshot};
+use anyhow::{Context, Result};
+use url::Url;
+
+#[cfg(test)]
+use mockall::automock;
+
+#[cfg_attr(test, allow(dead_code))]
+pub struct Rest {
+    base: Url,
+}
+
+#[cfg_attr(test, automock, allow(dead_code))]
+impl Rest {
+    pub fn new(base: &Url) -> Self {
+        Self { base: base.clone() }
+    }
+
+    /// Token and websocket endpoint for the public channels
+    pub fn bullet(&self) -> Result<Bullet> {
+        let url = self.base.join("/api/v1/bullet-public")?;
+
+        let envelope: Envelope<Bullet> = ureq::post(url.as_str())
+            .call()
+            .with_context(|| "Failed to request bullet")?
+            .into_json()?;
+
+        envelope.into_data()
+    }
+
+    pub fn snapshot(&self, symbol: &str) -> Result<Snapshot> {
+        let mut url = self.base.join("/api/v1/market/orderbook/level2_100")?;
+        url.query_pairs_mut().append_pair("symbol", symbol);
+
+        let envelope: Envelope<Snapshot> = ureq::get(url.as_str())
+            .call()
+            .with_context(|| "Failed to request snapshot")?
+            .into_json()?;
+
+        envelope.into_data()
+    }
+}
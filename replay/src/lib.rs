@@ -0,0 +1,88 @@
+pub mod provider;
+
+pub use provider::{Parser, Replay};
+
+use anyhow::{bail, Result};
+use common::{
+    chaos,
+    recorder::{self, Record},
+    registry::Registry,
+};
+use log::info;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Exchanges whose captures can be played back, with the parser of their live provider
+fn parser(exchange: &str) -> Option<(&'static str, Parser)> {
+    match exchange {
+        "binance" => Some(("Binance", |message, received| {
+            binance::parse(message, received).map(Some)
+        })),
+        "bitstamp" => Some(("Bitstamp", bitstamp::parse)),
+        _ => None,
+    }
+}
+
+/// Registers a replay in place of the live provider of every exchange found in the captures
+pub fn register(registry: &mut Registry, captures: &[PathBuf]) -> Result<()> {
+    let mut exchanges = BTreeMap::<String, Vec<Record>>::new();
+
+    for path in files(captures)? {
+        for record in recorder::read(&path)? {
+            exchanges
+                .entry(record.exchange.clone())
+                .or_default()
+                .push(record);
+        }
+    }
+
+    for (exchange, mut records) in exchanges {
+        let (name, parser) = match parser(exchange.as_str()) {
+            Some(parser) => parser,
+            None => bail!("no replay for {} captures", exchange),
+        };
+
+        info!("replay {} frames of {}", records.len(), exchange);
+
+        // several connections play back as one feed, in the order they were received
+        records.sort_by_key(|record| record.receive_timestamp);
+        let records = Arc::new(records);
+
+        let key = exchange.clone();
+        registry.register(exchange.as_str(), move |config, _| {
+            let provider = Box::new(Replay::new(
+                config.clone(),
+                name,
+                parser,
+                Arc::clone(&records),
+            ));
+            Ok(chaos::feed(&config, &key, provider))
+        });
+    }
+
+    Ok(())
+}
+
+/// Capture files, directories stand for the `.zst` files they hold
+fn files(captures: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    for path in captures {
+        if path.is_dir() {
+            let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<_, _>>()?;
+            entries.retain(|entry| entry.extension().and_then(|ext| ext.to_str()) == Some("zst"));
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(Path::new(path).to_path_buf());
+        }
+    }
+
+    Ok(files)
+}
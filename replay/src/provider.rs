@@ -0,0 +1,234 @@
+use anyhow::{anyhow, Result};
+use common::{clock, config::Speed, orderbook::Summary, recorder::Record, ConfigRef, Provider};
+use log::{info, warn};
+use parking_lot::Mutex;
+use std::{
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Parsing code of a live provider, none for frames that carry no book
+pub type Parser = fn(&str, u64) -> Result<Option<Summary>>;
+
+/// Plays captured frames back through the parser of the exchange that sent them
+pub struct Replay {
+    name: &'static str,
+    speed: Speed,
+    parser: Parser,
+    records: Arc<Vec<Record>>,
+    state: Mutex<State>,
+}
+
+/// How often the last book is handed out again once the capture is over
+const FINISHED: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+struct State {
+    next: usize,
+    /// Wall clock and capture timestamp the pace is measured from
+    origin: Option<(Instant, u64)>,
+    /// Book of the last frame played
+    last: Option<Summary>,
+}
+
+impl Provider for Replay {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn subscribe(&self) -> Result<()> {
+        info!("{} replay from frame {}", self.name, self.state.lock().next);
+        self.state.lock().origin = None;
+        Ok(())
+    }
+
+    fn unsubscribe(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn summary(&self) -> Result<Summary> {
+        loop {
+            let (record, deadline) = match self.next() {
+                Some(next) => next,
+                None => return self.finished(),
+            };
+
+            if let Some(deadline) = deadline {
+                thread::sleep(deadline.saturating_duration_since(Instant::now()));
+            }
+
+            let data = match (record.kind.as_str(), record.data.as_deref()) {
+                ("text" | "binary", Some(data)) => data,
+                _ => continue,
+            };
+
+            let received = clock::micros();
+            if let Some(mut summary) = (self.parser)(data, received)? {
+                // moved onto the replay clock, the exchange latency stays the captured one
+                if summary.event_timestamp != 0 {
+                    summary.event_timestamp = (summary.event_timestamp + received)
+                        .saturating_sub(record.receive_timestamp);
+                }
+
+                self.state.lock().last = Some(summary.clone());
+                return Ok(summary);
+            }
+        }
+    }
+}
+
+impl Replay {
+    pub fn new(
+        config: ConfigRef,
+        name: &'static str,
+        parser: Parser,
+        records: Arc<Vec<Record>>,
+    ) -> Self {
+        Self {
+            name,
+            speed: config.replay_speed(),
+            parser,
+            records,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Next frame and when it is due, none once the capture is over
+    fn next(&self) -> Option<(&Record, Option<Instant>)> {
+        let mut state = self.state.lock();
+
+        let record = self.records.get(state.next)?;
+        state.next += 1;
+
+        if state.next == self.records.len() {
+            warn!("{} capture finished, keeping its last book", self.name);
+        }
+
+        let (start, first) = *state
+            .origin
+            .get_or_insert((Instant::now(), record.receive_timestamp));
+        let elapsed = record.receive_timestamp.saturating_sub(first);
+
+        let deadline = match self.speed {
+            Speed::Realtime => Some(start + Duration::from_micros(elapsed)),
+            Speed::Times(factor) => {
+                Some(start + Duration::from_secs_f64(elapsed as f64 / 1e6 / factor))
+            }
+            Speed::Max => None,
+        };
+
+        Some((record, deadline))
+    }
+
+    /// The last book, paced so the merge does not spin on a finished capture
+    fn finished(&self) -> Result<Summary> {
+        thread::sleep(FINISHED);
+
+        let last = self.state.lock().last.clone();
+        match last {
+            Some(summary) => Ok(Summary {
+                event_timestamp: 0,
+                receive_timestamp: clock::micros(),
+                ..summary
+            }),
+            None => Err(anyhow!("{} capture holds no book", self.name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::config::Config;
+
+    fn record(receive_timestamp: u64, kind: &str, data: Option<&str>) -> Record {
+        Record {
+            receive_timestamp,
+            exchange: String::from("bitstamp"),
+            connection: 1,
+            kind: String::from(kind),
+            data: data.map(String::from),
+        }
+    }
+
+    fn replay(speed: &str) -> Result<Replay> {
+        let config = Config::load_from(["algo", "--replay-speed", speed])?;
+
+        let records = vec![
+            record(
+                1_000_000,
+                "text",
+                Some(
+                    r#"{"event":"bts:subscription_succeeded","channel":"order_book_ethbtc","data":{}}"#,
+                ),
+            ),
+            record(1_100_000, "ping", None),
+            record(
+                1_200_000,
+                "text",
+                Some(
+                    r#"{"data":{"timestamp":"1682624742","microtimestamp":"1682624742462361","bids":[["0.06466182","0.5"]],"asks":[["0.06468051","0.4"]]},"channel":"order_book_ethbtc","event":"data"}"#,
+                ),
+            ),
+            record(
+                1_300_000,
+                "text",
+                Some(
+                    r#"{"data":{"timestamp":"1682624743","microtimestamp":"1682624743000000","bids":[["0.06466000","1.5"]],"asks":[]},"channel":"order_book_ethbtc","event":"data"}"#,
+                ),
+            ),
+        ];
+
+        Ok(Replay::new(
+            Arc::new(config),
+            "Bitstamp",
+            bitstamp::parse,
+            Arc::new(records),
+        ))
+    }
+
+    #[test]
+    fn test_summary_max() -> Result<()> {
+        let provider = replay("max")?;
+        provider.subscribe()?;
+
+        let summary = provider.summary()?;
+        // as far from the replay receipt as the event was from the captured one
+        assert_eq!(
+            summary.event_timestamp - summary.receive_timestamp,
+            1682624742462361 - 1_200_000
+        );
+        assert_eq!(summary.bids[0].exchange, "Bitstamp");
+        assert_eq!(summary.bids[0].price, 0.06466182);
+        assert_eq!(summary.asks[0].amount, 0.4);
+
+        let summary = provider.summary()?;
+        assert_eq!(summary.bids[0].amount, 1.5);
+        assert!(summary.asks.is_empty());
+
+        // the capture over, its last book stays
+        let summary = provider.summary()?;
+        assert_eq!(summary.bids[0].amount, 1.5);
+        assert_eq!(summary.event_timestamp, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_paced() -> Result<()> {
+        let provider = replay("4")?;
+        provider.subscribe()?;
+
+        let start = Instant::now();
+        provider.summary()?;
+        provider.summary()?;
+
+        // 300ms of capture at four times the speed
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(75), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(300), "{:?}", elapsed);
+
+        Ok(())
+    }
+}
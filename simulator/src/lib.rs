@@ -0,0 +1,13 @@
+pub mod provider;
+
+pub use provider::Simulator;
+
+use common::{chaos, registry::Registry};
+
+/// Registers the provider under `simulator`
+pub fn register(registry: &mut Registry) {
+    registry.register("simulator", |config, _| {
+        let provider = Box::new(Simulator::new(config.clone()));
+        Ok(chaos::feed(&config, "simulator", provider))
+    });
+}
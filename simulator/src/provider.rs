@@ -0,0 +1,268 @@
+use anyhow::Result;
+use common::{
+    clock,
+    config::Simulation,
+    orderbook::{Level, Summary},
+    ConfigRef, Provider,
+};
+use log::info;
+use parking_lot::Mutex;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_distr::{Distribution, Normal};
+use std::{thread, time::Instant};
+
+const NAME: &str = "Simulator";
+
+/// Updates a liquidity shock takes to wear off
+const SHOCK_UPDATES: u32 = 20;
+
+/// Synthetic exchange quoting around a random walk of the mid
+pub struct Simulator {
+    simulation: Simulation,
+    depth: usize,
+    state: Mutex<State>,
+}
+
+struct State {
+    rng: ChaCha8Rng,
+    mid: f64,
+    shock: Option<Shock>,
+    /// When the next update is due
+    deadline: Option<Instant>,
+}
+
+/// Liquidity pulled from one side, coming back over the following updates
+struct Shock {
+    bids: bool,
+    remaining: u32,
+}
+
+impl Provider for Simulator {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn subscribe(&self) -> Result<()> {
+        self.state.lock().deadline = None;
+        Ok(())
+    }
+
+    fn unsubscribe(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn summary(&self) -> Result<Summary> {
+        let deadline = {
+            let mut state = self.state.lock();
+            let now = Instant::now();
+            let deadline = state.deadline.unwrap_or(now);
+            // a slow reader gets the next update on time rather than a burst
+            state.deadline = Some(deadline.max(now) + self.simulation.interval);
+            deadline
+        };
+
+        thread::sleep(deadline.saturating_duration_since(Instant::now()));
+
+        self.state.lock().update(&self.simulation, self.depth)
+    }
+}
+
+impl Simulator {
+    pub fn new(config: ConfigRef) -> Self {
+        let simulation = config.simulation();
+
+        let seed = simulation.seed.unwrap_or_else(rand::random);
+        info!("simulator seed {}", seed);
+
+        Self {
+            simulation,
+            depth: config.depth("simulator").unwrap_or(20),
+            state: Mutex::new(State {
+                rng: ChaCha8Rng::seed_from_u64(seed),
+                mid: simulation.mid,
+                shock: None,
+                deadline: None,
+            }),
+        }
+    }
+}
+
+impl State {
+    /// Moves the mid and quotes a fresh book around it
+    fn update(&mut self, simulation: &Simulation, depth: usize) -> Result<Summary> {
+        let step = Normal::new(0.0, simulation.volatility / 1e4)?;
+        self.mid *= step.sample(&mut self.rng).exp();
+
+        if self.shock.is_none() && self.rng.gen_bool(simulation.shock) {
+            // the mid jumps as the liquidity leaves
+            self.mid *= (step.sample(&mut self.rng) * 10.0).exp();
+            self.shock = Some(Shock {
+                bids: self.rng.gen(),
+                remaining: SHOCK_UPDATES,
+            });
+        }
+
+        let half = self.mid * simulation.spread / 2e4;
+        let tick = self.mid * simulation.tick / 1e4;
+
+        let (bid, ask) = if self.rng.gen_bool(simulation.cross) {
+            (self.mid + half + tick, self.mid - half - tick)
+        } else {
+            (self.mid - half, self.mid + half)
+        };
+
+        let (bids, asks) = match &self.shock {
+            Some(shock) => {
+                let left = 1.0 - 0.95 * shock.remaining as f64 / SHOCK_UPDATES as f64;
+                match shock.bids {
+                    true => (left, 1.0),
+                    false => (1.0, left),
+                }
+            }
+            None => (1.0, 1.0),
+        };
+
+        let timestamp = clock::micros();
+        let mut summary = Summary {
+            event_timestamp: timestamp,
+            receive_timestamp: timestamp,
+            ..Default::default()
+        };
+
+        for i in 0..depth {
+            let amount = simulation.amount * (1.0 + simulation.growth * i as f64);
+            summary
+                .bids
+                .push(self.level(bid - tick * i as f64, amount * bids));
+            summary
+                .asks
+                .push(self.level(ask + tick * i as f64, amount * asks));
+        }
+
+        if let Some(shock) = &mut self.shock {
+            shock.remaining -= 1;
+            if shock.remaining == 0 {
+                self.shock = None;
+            }
+        }
+
+        Ok(summary)
+    }
+
+    fn level(&mut self, price: f64, amount: f64) -> Level {
+        Level {
+            exchange: String::from(NAME),
+            price: round(price),
+            amount: round(amount * self.rng.gen_range(0.5..1.5)),
+            ..Default::default()
+        }
+    }
+}
+
+/// Eight decimals, as the exchanges quote
+fn round(value: f64) -> f64 {
+    (value * 1e8).round() / 1e8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::config::Config;
+    use std::sync::Arc;
+
+    fn simulator(args: &[&str]) -> Result<Simulator> {
+        let mut argv = vec!["algo"];
+        argv.extend_from_slice(args);
+        if !args.contains(&"--simulator-interval") {
+            argv.extend_from_slice(&["--simulator-interval", "0"]);
+        }
+        Ok(Simulator::new(Arc::new(Config::load_from(argv)?)))
+    }
+
+    fn total(levels: &[Level]) -> f64 {
+        levels.iter().map(|level| level.amount).sum()
+    }
+
+    #[test]
+    fn test_seed_reproducible() -> Result<()> {
+        let first = simulator(&["--simulator-seed", "7"])?;
+        let second = simulator(&["--simulator-seed", "7"])?;
+        let other = simulator(&["--simulator-seed", "8"])?;
+
+        for _ in 0..50 {
+            let summary = first.summary()?;
+            let same = second.summary()?;
+            assert_eq!(summary.bids, same.bids);
+            assert_eq!(summary.asks, same.asks);
+            assert_ne!(summary.bids, other.summary()?.bids);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary() -> Result<()> {
+        let provider = simulator(&[
+            "--simulator-seed",
+            "1",
+            "--simulator-shock",
+            "0",
+            "--simulator-cross",
+            "0",
+        ])?;
+        provider.subscribe()?;
+
+        for _ in 0..100 {
+            let summary = provider.summary()?;
+            assert_eq!(summary.bids.len(), 20);
+            assert_eq!(summary.asks.len(), 20);
+            assert_eq!(summary.bids[0].exchange, "Simulator");
+            assert!(summary.bids[0].price < summary.asks[0].price);
+            assert!(summary.bids.windows(2).all(|w| w[0].price > w[1].price));
+            assert!(summary.asks.windows(2).all(|w| w[0].price < w[1].price));
+            assert!(summary.bids.iter().all(|level| level.amount > 0.0));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_crossed() -> Result<()> {
+        let provider = simulator(&["--simulator-seed", "1", "--simulator-cross", "1"])?;
+
+        let summary = provider.summary()?;
+        assert!(summary.bids[0].price > summary.asks[0].price);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_shock() -> Result<()> {
+        let provider = simulator(&["--simulator-seed", "1", "--simulator-shock", "1"])?;
+
+        let summary = provider.summary()?;
+        let (bids, asks) = (total(&summary.bids), total(&summary.asks));
+        assert!(bids.min(asks) < 0.2 * bids.max(asks), "{} {}", bids, asks);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_paced() -> Result<()> {
+        let provider = simulator(&["--simulator-interval", "50"])?;
+        provider.subscribe()?;
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            provider.summary()?;
+        }
+
+        // the first update is immediate
+        let elapsed = start.elapsed();
+        assert!(elapsed.as_millis() >= 100, "{:?}", elapsed);
+        assert!(elapsed.as_millis() < 500, "{:?}", elapsed);
+
+        Ok(())
+    }
+}
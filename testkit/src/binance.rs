@@ -0,0 +1,20 @@
+//! Binance partial depth stream, the subscription is the URL so nothing is read
+
+/// Partial depth frame, levels as `(price, amount)`
+pub fn depth(update_id: u64, bids: &[(f64, f64)], asks: &[(f64, f64)]) -> String {
+    format!(
+        r#"{{"lastUpdateId":{},"bids":{},"asks":{}}}"#,
+        update_id,
+        levels(bids),
+        levels(asks)
+    )
+}
+
+/// Levels the way exchanges send them, as arrays of strings
+pub(crate) fn levels(levels: &[(f64, f64)]) -> String {
+    let levels: Vec<String> = levels
+        .iter()
+        .map(|(price, amount)| format!(r#"["{:.8}","{:.8}"]"#, price, amount))
+        .collect();
+    format!("[{}]", levels.join(","))
+}
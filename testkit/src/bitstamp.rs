@@ -0,0 +1,37 @@
+//! Bitstamp `bts:subscribe` protocol
+
+use crate::binance::levels;
+
+/// Order book event of `pair`, levels as `(price, amount)`
+pub fn book(pair: &str, microtimestamp: u64, bids: &[(f64, f64)], asks: &[(f64, f64)]) -> String {
+    format!(
+        r#"{{"data":{{"timestamp":"{}","microtimestamp":"{}","bids":{},"asks":{}}},"channel":"order_book_{}","event":"data"}}"#,
+        microtimestamp / 1_000_000,
+        microtimestamp,
+        levels(bids),
+        levels(asks),
+        pair
+    )
+}
+
+/// Answer to a subscription request, none for anything else
+pub(crate) fn reply(request: &str) -> Option<String> {
+    let event = if request.contains("\"bts:subscribe\"") {
+        "bts:subscription_succeeded"
+    } else if request.contains("\"bts:unsubscribe\"") {
+        "bts:unsubscription_succeeded"
+    } else {
+        return None;
+    };
+
+    let channel = request
+        .split("\"channel\":\"")
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .unwrap_or_default();
+
+    Some(format!(
+        r#"{{"event":"{}","channel":"{}","data":{{}}}}"#,
+        event, channel
+    ))
+}
@@ -0,0 +1,179 @@
+pub mod binance;
+pub mod bitstamp;
+
+use anyhow::Result;
+use log::debug;
+use parking_lot::Mutex;
+use std::{
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+use tungstenite::{accept, Message, WebSocket};
+use url::Url;
+
+/// What the server does next on a connection
+#[derive(Clone, Debug)]
+pub enum Step {
+    /// Text frame, sent as is so malformed payloads are scripted the same way
+    Send(String),
+    /// Binary frame
+    Binary(Vec<u8>),
+    /// Waits before the next step
+    Pause(Duration),
+    /// Drops the TCP connection without a close handshake
+    Disconnect,
+    /// Starts the websocket close handshake
+    Close,
+}
+
+#[derive(Clone, Copy)]
+enum Protocol {
+    Binance,
+    Bitstamp,
+}
+
+/// Exchange websocket server on a local port, the n-th connection plays the n-th script
+///
+/// Once its script is over a connection stays open, answering subscriptions, until the client leaves
+/// or the server is dropped.
+pub struct MockServer {
+    address: SocketAddr,
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    scripts: Vec<Vec<Step>>,
+    connections: AtomicUsize,
+    streams: Mutex<Vec<TcpStream>>,
+    closing: AtomicBool,
+}
+
+impl MockServer {
+    /// Speaks the Binance depth stream
+    pub fn binance(scripts: Vec<Vec<Step>>) -> Result<Self> {
+        Self::start(Protocol::Binance, scripts)
+    }
+
+    /// Speaks the Bitstamp protocol, scripts start once the client subscribed
+    pub fn bitstamp(scripts: Vec<Vec<Step>>) -> Result<Self> {
+        Self::start(Protocol::Bitstamp, scripts)
+    }
+
+    fn start(protocol: Protocol, scripts: Vec<Vec<Step>>) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+
+        let shared = Arc::new(Shared {
+            scripts,
+            connections: AtomicUsize::new(0),
+            streams: Mutex::default(),
+            closing: AtomicBool::new(false),
+        });
+
+        let accepting = Arc::clone(&shared);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if accepting.closing.load(Ordering::Acquire) {
+                    break;
+                }
+
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        debug!("mock accept failed - {}", e);
+                        continue;
+                    }
+                };
+
+                let index = accepting.connections.fetch_add(1, Ordering::AcqRel);
+                let script = accepting.scripts.get(index).cloned().unwrap_or_default();
+
+                if let Ok(clone) = stream.try_clone() {
+                    accepting.streams.lock().push(clone);
+                }
+
+                thread::spawn(move || {
+                    if let Err(e) = serve(protocol, stream, script) {
+                        debug!("mock connection {} ended - {}", index, e);
+                    }
+                });
+            }
+        });
+
+        Ok(Self { address, shared })
+    }
+
+    /// Where clients connect, a `ws://` URL with an empty path
+    pub fn url(&self) -> Url {
+        Url::parse(format!("ws://{}/", self.address).as_str()).expect("invalid mock url")
+    }
+
+    /// Connections accepted so far
+    pub fn connections(&self) -> usize {
+        self.shared.connections.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for MockServer {
+    /// Cuts every connection so the clients blocked on a read fail instead of waiting forever
+    fn drop(&mut self) {
+        self.shared.closing.store(true, Ordering::Release);
+
+        for stream in self.shared.streams.lock().drain(..) {
+            stream.shutdown(Shutdown::Both).ok();
+        }
+
+        // wakes the accepting thread up
+        TcpStream::connect(self.address).ok();
+    }
+}
+
+fn serve(protocol: Protocol, stream: TcpStream, script: Vec<Step>) -> Result<()> {
+    let mut socket = accept(stream)?;
+
+    if let Protocol::Bitstamp = protocol {
+        // books only flow once subscribed
+        while !answer(&mut socket)? {}
+    }
+
+    for step in script {
+        match step {
+            Step::Send(text) => socket.write_message(Message::Text(text))?,
+            Step::Binary(data) => socket.write_message(Message::Binary(data))?,
+            Step::Pause(duration) => thread::sleep(duration),
+            Step::Disconnect => {
+                socket.get_ref().shutdown(Shutdown::Both)?;
+                return Ok(());
+            }
+            Step::Close => socket.close(None)?,
+        }
+    }
+
+    // reading is what answers pings and completes a close handshake
+    loop {
+        match protocol {
+            Protocol::Binance => {
+                socket.read_message()?;
+            }
+            Protocol::Bitstamp => {
+                answer(&mut socket)?;
+            }
+        }
+    }
+}
+
+/// Reads a request and answers it when it is a subscription
+fn answer(socket: &mut WebSocket<TcpStream>) -> Result<bool> {
+    if let Message::Text(request) = socket.read_message()? {
+        if let Some(reply) = bitstamp::reply(request.as_str()) {
+            socket.write_message(Message::Text(reply))?;
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}